@@ -0,0 +1,298 @@
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use clap::Parser;
+use pipenv2uv::errors::ConversionError;
+use pipenv2uv::models::options::parse_group_names;
+use pipenv2uv::models::project::{
+    normalize_project_name, normalize_version, ProjectMetadata, ProjectVersion,
+};
+use pipenv2uv::{
+    BuildBackend, DirectReferenceStyle, DuplicatePolicy, ExportOptions, LockPolicy,
+    PrereleasePolicy, ScriptStyle, PIPFILE,
+};
+use std::path::{Path, PathBuf};
+
+const CONVERSION: &str = "Conversion";
+const LOCK: &str = "Pipfile.lock";
+const PROJECT: &str = "Project metadata";
+
+fn parse_project_version(version: &str) -> Result<String, String> {
+    normalize_version(version).ok_or_else(|| "not a PEP 440 version".to_string())
+}
+
+fn parse_project_name(name: &str) -> Result<String, String> {
+    normalize_project_name(name).ok_or_else(|| "no letters or digits in the name".to_string())
+}
+
+/// Converts a Pipfile into a pyproject.toml for uv
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// Pipfile to convert, or the directory containing it
+    #[arg(default_value = PIPFILE)]
+    pub input: PathBuf,
+
+    /// File to write, or a directory to write pyproject.toml into [default: next to the Pipfile]
+    #[arg(short, long, value_name = "PATH", conflicts_with = "stdout")]
+    pub output: Option<PathBuf>,
+
+    /// Print pyproject.toml to stdout instead of writing it
+    #[arg(long)]
+    pub stdout: bool,
+
+    /// Overwrite existing files instead of writing pyproject-new-<N>.toml
+    #[arg(long)]
+    pub force: bool,
+
+    /// Update an existing pyproject.toml in place, keeping its other settings and comments
+    #[arg(long)]
+    pub merge: bool,
+
+    /// Show which files would be written without writing them
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// Only print errors
+    #[arg(short, long, conflicts_with = "verbose")]
+    pub quiet: bool,
+
+    /// Also print every package found
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// How `file = ...` dependencies are written
+    #[arg(
+        long,
+        value_name = "STYLE",
+        default_value = "sources",
+        help_heading = CONVERSION,
+        value_parser = PossibleValuesParser::new(["sources", "pep508"])
+            .map(|name| DirectReferenceStyle::from_name(&name).unwrap_or_default()),
+    )]
+    pub direct_references: DirectReferenceStyle,
+
+    /// Names of dependency groups for Pipenv categories, e.g. `docs=documentation,tests=test`
+    #[arg(long, value_name = "MAPPING", help_heading = CONVERSION)]
+    pub dependency_groups: Option<String>,
+
+    /// uv prerelease policy written when the Pipfile allows pre-releases
+    #[arg(
+        long,
+        value_name = "POLICY",
+        default_value = "allow",
+        help_heading = CONVERSION,
+        value_parser = PossibleValuesParser::new(["allow", "if-necessary-or-explicit"])
+            .map(|name| PrereleasePolicy::from_name(&name).unwrap_or_default()),
+    )]
+    pub prerelease: PrereleasePolicy,
+
+    /// Handling of packages listed twice under equivalent names
+    #[arg(
+        long,
+        value_name = "POLICY",
+        default_value = "report",
+        help_heading = CONVERSION,
+        value_parser = PossibleValuesParser::new(["report", "merge", "reject"])
+            .map(|name| DuplicatePolicy::from_name(&name).unwrap_or_default()),
+    )]
+    pub duplicates: DuplicatePolicy,
+
+    /// Form in which Pipfile scripts are written
+    #[arg(
+        long,
+        value_name = "STYLE",
+        default_value = "poe",
+        help_heading = CONVERSION,
+        value_parser = PossibleValuesParser::new(["poe", "makefile", "justfile", "project-scripts"])
+            .map(|name| ScriptStyle::from_name(&name).unwrap_or_default()),
+    )]
+    pub scripts: ScriptStyle,
+
+    /// Build backend written when the project is an installable package
+    #[arg(
+        long,
+        value_name = "BACKEND",
+        default_value = "setuptools",
+        help_heading = CONVERSION,
+        value_parser = PossibleValuesParser::new(["setuptools", "hatchling", "uv_build", "flit"])
+            .map(|name| BuildBackend::from_name(&name).unwrap_or_default()),
+    )]
+    pub build_backend: BuildBackend,
+
+    /// Write PEP 503 normalized package names
+    #[arg(long, help_heading = CONVERSION)]
+    pub normalize_names: bool,
+
+    /// Write the output even when some Pipfile entries are broken
+    #[arg(long, help_heading = CONVERSION)]
+    pub best_effort: bool,
+
+    /// Write a .env.example with the credential variables of indexes
+    #[arg(long, help_heading = CONVERSION)]
+    pub write_env_example: bool,
+
+    /// Replace `*` versions with `==`, `>=` or `~=` the locked version
+    #[arg(
+        long,
+        value_name = "POLICY",
+        help_heading = LOCK,
+        value_parser = PossibleValuesParser::new(["exact", "lower-bound", "compatible"])
+            .map(|name| LockPolicy::from_name(&name).unwrap_or(LockPolicy::Exact)),
+    )]
+    pub lock_policy: Option<LockPolicy>,
+
+    /// Pin every locked package to its exact version so that `uv lock` reproduces Pipfile.lock
+    #[arg(long, help_heading = LOCK)]
+    pub uv_lock: bool,
+
+    /// Check the versions and hashes in uv.lock against Pipfile.lock instead of converting
+    #[arg(long, help_heading = LOCK, conflicts_with_all = ["output", "stdout", "merge"])]
+    pub verify_uv_lock: bool,
+
+    /// Constrain transitive packages to their locked versions
+    #[arg(long, help_heading = LOCK)]
+    pub lock_constraints: bool,
+
+    /// Stop when Pipfile.lock does not match the Pipfile
+    #[arg(long, help_heading = LOCK)]
+    pub strict_lock: bool,
+
+    /// Project name [default: from setup.cfg, setup.py, the package or the directory name]
+    #[arg(long, help_heading = PROJECT, value_parser = parse_project_name)]
+    pub name: Option<String>,
+
+    /// Project version [default: from setup.cfg, setup.py, `__version__` or the latest git tag]
+    #[arg(long, value_name = "VERSION", help_heading = PROJECT, value_parser = parse_project_version)]
+    pub project_version: Option<String>,
+
+    /// Project description [default: the first paragraph of the README]
+    #[arg(long, help_heading = PROJECT)]
+    pub description: Option<String>,
+
+    /// README file, relative to the Pipfile [default: README.md, README.rst or README.txt]
+    #[arg(long, value_name = "PATH", help_heading = PROJECT)]
+    pub readme: Option<String>,
+
+    /// Metadata not to take from git and the LICENSE file
+    #[arg(
+        long,
+        value_name = "FIELDS",
+        value_delimiter = ',',
+        help_heading = PROJECT,
+        value_parser = PossibleValuesParser::new(["authors", "license", "urls"]),
+    )]
+    pub skip_metadata: Vec<String>,
+}
+
+impl Cli {
+    /// Project metadata found next to the Pipfile, with the values given on the command line
+    pub fn project_metadata(
+        &self,
+        project_directory: &Path,
+    ) -> Result<ProjectMetadata, ConversionError> {
+        let mut project: ProjectMetadata = ProjectMetadata::discover(project_directory);
+        if let Some(readme) = &self.readme {
+            project
+                .set_readme(project_directory, readme)
+                .map_err(|diagnostic| {
+                    let path: String = project_directory.join(readme).display().to_string();
+                    ConversionError::input(&path, "", vec![diagnostic])
+                })?;
+        }
+        if let Some(name) = &self.name {
+            project.name = name.clone();
+        }
+        if let Some(version) = &self.project_version {
+            project.version = ProjectVersion::Static(version.clone());
+        }
+        if let Some(description) = &self.description {
+            project.description = description.clone();
+        }
+        for field in &self.skip_metadata {
+            match field.as_str() {
+                "authors" => project.authors.clear(),
+                "license" => project.license = None,
+                _ => project.urls.clear(),
+            }
+        }
+        Ok(project)
+    }
+
+    pub fn export_options(
+        &self,
+        project_directory: &Path,
+    ) -> Result<ExportOptions, ConversionError> {
+        Ok(ExportOptions {
+            direct_references: self.direct_references,
+            prerelease: self.prerelease,
+            group_names: self
+                .dependency_groups
+                .as_deref()
+                .map(parse_group_names)
+                .unwrap_or_default(),
+            lock_policy: self.lock_policy,
+            uv_lock: self.uv_lock,
+            lock_constraints: self.lock_constraints,
+            strict_lock: self.strict_lock,
+            duplicates: self.duplicates,
+            normalize_names: self.normalize_names,
+            best_effort: self.best_effort,
+            scripts: self.scripts,
+            build_backend: self.build_backend,
+            project: self.project_metadata(project_directory)?,
+        })
+    }
+
+    /// Whether Pipfile.lock is needed by any of the requested options
+    pub fn uses_lock(&self) -> bool {
+        self.lock_policy.is_some() || self.uv_lock || self.lock_constraints
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_arguments() {
+        let cli = Cli::parse_from([
+            "Pipenv2Uv",
+            "project/Pipfile",
+            "-o",
+            "output/",
+            "--lock-policy",
+            "compatible",
+            "--dependency-groups",
+            "docs=documentation",
+            "--name",
+            "My_Service",
+            "--project-version",
+            "v1.0",
+            "--skip-metadata",
+            "authors,urls",
+        ]);
+
+        assert_eq!(cli.input, PathBuf::from("project/Pipfile"));
+        assert_eq!(cli.output, Some(PathBuf::from("output/")));
+        assert!(cli.uses_lock());
+
+        let options = cli.export_options(Path::new("project")).unwrap();
+        assert_eq!(options.lock_policy, Some(LockPolicy::Compatible));
+        assert_eq!(options.group_names["docs"], "documentation");
+        assert_eq!(options.direct_references, DirectReferenceStyle::Sources);
+        assert_eq!(options.project.name, "my-service");
+        assert_eq!(
+            options.project.version,
+            ProjectVersion::Static("1.0".to_string())
+        );
+        assert!(options.project.authors.is_empty());
+    }
+
+    #[test]
+    fn test_reject_conflicting_arguments() {
+        assert!(Cli::try_parse_from(["Pipenv2Uv", "--stdout", "-o", "out.toml"]).is_err());
+        assert!(Cli::try_parse_from(["Pipenv2Uv", "--quiet", "--verbose"]).is_err());
+        assert!(Cli::try_parse_from(["Pipenv2Uv", "--lock-policy", "latest"]).is_err());
+        assert!(Cli::try_parse_from(["Pipenv2Uv", "--name", "__"]).is_err());
+        assert!(Cli::try_parse_from(["Pipenv2Uv", "--verify-uv-lock", "--merge"]).is_err());
+    }
+}
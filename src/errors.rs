@@ -0,0 +1,244 @@
+use std::fmt;
+use std::ops::Range;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    /// The entry is invalid and left out of the output
+    Error,
+    /// Something the user should check in the converted output
+    Warning,
+    /// Hint on how to use the converted output
+    Info,
+}
+
+/// Problem found in an input file, located by a byte span into its content,
+/// or a note on the conversion without a span
+#[derive(Debug)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub span: Option<Range<usize>>,
+}
+
+impl Diagnostic {
+    pub fn new(message: &str, span: Option<Range<usize>>) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            message: message.to_string(),
+            span,
+        }
+    }
+
+    pub fn warning(message: String) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            message,
+            span: None,
+        }
+    }
+
+    pub fn info(message: String) -> Self {
+        Diagnostic {
+            severity: Severity::Info,
+            message,
+            span: None,
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        self.severity == Severity::Error
+    }
+
+    /// Builds a diagnostic from a 1-based line and column, as reported by serde_json
+    pub fn at_line(message: &str, content: &str, line: usize, column: usize) -> Self {
+        let line_start: usize = content
+            .split_inclusive('\n')
+            .take(line.saturating_sub(1))
+            .map(|line| line.len())
+            .sum();
+        let start: usize = (line_start + column.saturating_sub(1)).min(content.len());
+        Diagnostic::new(message, Some(start..start))
+    }
+
+    /// serde_json only reports the line and column of a syntax error
+    pub fn from_json_error(error: &serde_json::Error, content: &str) -> Self {
+        let error_text: String = error.to_string();
+        // the location is rendered separately, so it is cut from the message
+        let message: &str = error_text
+            .rsplit_once(" at line ")
+            .map(|(message, _)| message)
+            .unwrap_or(&error_text);
+        Diagnostic::at_line(message, content, error.line(), error.column())
+    }
+
+    /// 1-based line and column where the span starts
+    pub fn location(&self, content: &str) -> Option<(usize, usize)> {
+        let start: usize = self.span.as_ref()?.start.min(content.len());
+        let before: &str = content.get(..start)?;
+        let line: usize = before.matches('\n').count() + 1;
+        let line_start: usize = before.rfind('\n').map(|index| index + 1).unwrap_or(0);
+        let column: usize = before[line_start..].chars().count() + 1;
+        Some((line, column))
+    }
+
+    /// Renders the message followed by the offending source line, like rustc does
+    pub fn render(&self, file_name: &str, content: &str) -> String {
+        let Some((line, column)) = self.location(content) else {
+            return format!("{}\n --> {}", self.message, file_name);
+        };
+
+        let source_line: &str = content.lines().nth(line - 1).unwrap_or_default();
+        let span_length: usize = self
+            .span
+            .as_ref()
+            .and_then(|span| content.get(span.clone()))
+            .map(|spanned| spanned.lines().next().unwrap_or_default().chars().count())
+            .unwrap_or_default()
+            .max(1);
+        let gutter: String = " ".repeat(line.to_string().len());
+
+        format!(
+            "{message}\n{gutter}--> {file}:{line}:{column}\n{gutter} |\n{line} | {source}\n{gutter} | {padding}{carets}",
+            message = self.message,
+            file = file_name,
+            source = source_line,
+            padding = " ".repeat(column - 1),
+            carets = "^".repeat(span_length),
+        )
+    }
+}
+
+impl From<toml_edit::TomlError> for Diagnostic {
+    fn from(error: toml_edit::TomlError) -> Self {
+        Diagnostic::new(error.message(), error.span())
+    }
+}
+
+/// Renders all diagnostics of a file, each after the `label: ` the caller prints first
+pub fn render_report(
+    file_name: &str,
+    content: &str,
+    diagnostics: &[Diagnostic],
+    label: &str,
+) -> String {
+    let mut report: String = diagnostics
+        .iter()
+        .map(|diagnostic| diagnostic.render(file_name, content))
+        .collect::<Vec<String>>()
+        .join(&format!("\n\n{}: ", label));
+    if diagnostics.len() > 1 {
+        report.push_str(&format!(
+            "\n\n{} problems found in {}",
+            diagnostics.len(),
+            file_name
+        ));
+    }
+    report
+}
+
+pub enum ConversionError {
+    /// An input or output file could not be read or written
+    Io {
+        path: String,
+        error: std::io::Error,
+    },
+    /// The content of an input file is invalid, every problem found is kept
+    Input {
+        file_name: String,
+        content: String,
+        diagnostics: Vec<Diagnostic>,
+    },
+    StaleLock,
+    DuplicatePackage(String),
+}
+
+impl ConversionError {
+    pub fn io(path: &str, error: std::io::Error) -> Self {
+        ConversionError::Io {
+            path: path.to_string(),
+            error,
+        }
+    }
+
+    pub fn input(file_name: &str, content: &str, diagnostics: Vec<Diagnostic>) -> Self {
+        ConversionError::Input {
+            file_name: file_name.to_string(),
+            content: content.to_string(),
+            diagnostics,
+        }
+    }
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConversionError::Io { path, error } => write!(f, "{}: {}", path, error),
+            ConversionError::Input {
+                file_name,
+                content,
+                diagnostics,
+            } => write!(
+                f,
+                "{}",
+                render_report(file_name, content, diagnostics, "error")
+            ),
+            ConversionError::StaleLock => {
+                write!(f, "Pipfile.lock is out of date, run `pipenv lock` first")
+            }
+            ConversionError::DuplicatePackage(description) => {
+                write!(f, "Package {}", description)
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+impl std::error::Error for ConversionError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_diagnostic() {
+        let content = "[packages]\nrequests = \"=>2.0\"\n";
+        let start = content.find("\"=>").unwrap();
+        let diagnostic = Diagnostic::new("Invalid version", Some(start..start + 7));
+
+        assert_eq!(diagnostic.location(content), Some((2, 12)));
+        assert_eq!(
+            diagnostic.render("Pipfile", content),
+            "Invalid version\n --> Pipfile:2:12\n  |\n2 | requests = \"=>2.0\"\n  |            ^^^^^^^"
+        );
+    }
+
+    #[test]
+    fn test_render_report() {
+        let content = "[packages]\na = \"?\"\nb = 1\n";
+        let diagnostics = vec![
+            Diagnostic::new("Invalid version of package a", Some(15..18)),
+            Diagnostic::new("Unsupported specification for package b", Some(23..24)),
+        ];
+
+        let report = render_report("Pipfile", content, &diagnostics, "error");
+
+        assert!(report.starts_with("Invalid version of package a\n --> Pipfile:2:5"));
+        assert!(
+            report.contains("\n\nerror: Unsupported specification for package b\n --> Pipfile:3:5")
+        );
+        assert!(report.ends_with("2 problems found in Pipfile"));
+    }
+
+    #[test]
+    fn test_diagnostic_at_line() {
+        let content = "{\n  \"default\": [\n}";
+        let diagnostic = Diagnostic::at_line("Expected value", content, 3, 1);
+
+        assert_eq!(diagnostic.location(content), Some((3, 1)));
+    }
+}
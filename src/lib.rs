@@ -0,0 +1,285 @@
+pub mod errors;
+pub mod log;
+pub mod merge;
+pub mod models;
+pub mod processors;
+
+use errors::{ConversionError, Diagnostic};
+use models::lock::PipfileLock;
+use models::package::Package;
+use models::pipenv::PipenvRequirements;
+use models::script::Script;
+use models::source::Source;
+use processors::BufferResultEnum;
+
+pub use models::options::{
+    BuildBackend, DirectReferenceStyle, DuplicatePolicy, ExportOptions, LockPolicy,
+    PrereleasePolicy, ScriptStyle,
+};
+pub use models::pipenv_content::{PipenvContent, PipenvUVInterface};
+
+pub const PIPFILE: &str = "Pipfile";
+pub const PIPFILE_LOCK: &str = "Pipfile.lock";
+pub const UV_LOCK: &str = "uv.lock";
+
+/// Files produced from a Pipfile, the caller decides where they are written
+pub struct Conversion {
+    pub pyproject: String,
+    pub credential_variables: Vec<(String, String)>,
+    // Makefile or justfile with the Pipfile scripts, see `ScriptStyle::file_name`
+    pub scripts_file: Option<String>,
+    // notes on the conversion, errors are entries left out with `ExportOptions::best_effort`
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Parses Pipfile content, recording every problem instead of stopping at the first
+pub fn parse_pipfile(content: &str, options: ExportOptions) -> (PipenvContent, Vec<Diagnostic>) {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    // unlike DocumentMut, Document keeps the spans used to point at problems
+    let document = processors::parse_document_recovering(content, &mut diagnostics);
+
+    let mut sources: Vec<Source> = Vec::new();
+    let mut packages: Vec<Package> = Vec::new();
+    let mut scripts: Vec<Script> = Vec::new();
+
+    for (block_name, block) in document.iter() {
+        match processors::process_block(block_name, block, &mut diagnostics) {
+            BufferResultEnum::Sources(processed_sources) => {
+                sources.extend(processed_sources);
+            }
+            BufferResultEnum::Packages(processed_packages) => {
+                packages.extend(processed_packages);
+            }
+            BufferResultEnum::Scripts(processed_scripts) => {
+                scripts.extend(processed_scripts);
+            }
+            _ => {}
+        }
+    }
+
+    let pipenv =
+        processors::parse_pipenv_settings(document.get("requires"), document.get("pipenv"));
+
+    // syntax errors are not found in file order, the report is
+    diagnostics.sort_by_key(|diagnostic| diagnostic.span.as_ref().map(|span| span.start));
+
+    let pipenv_content = PipenvContent {
+        sources,
+        packages,
+        pipenv,
+        constraints: Vec::new(),
+        scripts,
+        options,
+    };
+    (pipenv_content, diagnostics)
+}
+
+/// Converts Pipfile content, and optionally its Pipfile.lock content, into pyproject.toml content
+pub fn convert(
+    pipfile: &str,
+    pipfile_lock: Option<&str>,
+    options: ExportOptions,
+) -> Result<Conversion, ConversionError> {
+    let (mut file_content, mut diagnostics) = parse_pipfile(pipfile, options);
+    if diagnostics.iter().any(Diagnostic::is_error) && !file_content.options.best_effort {
+        diagnostics.retain(Diagnostic::is_error);
+        return Err(ConversionError::input(PIPFILE, pipfile, diagnostics));
+    }
+
+    file_content.packages = processors::resolve_duplicates(
+        std::mem::take(&mut file_content.packages),
+        file_content.options.duplicates,
+        file_content.options.normalize_names,
+        &mut diagnostics,
+    )?;
+
+    let mut lock: Option<PipfileLock> = None;
+    if let Some(lock_content) = pipfile_lock {
+        let pipfile_lock = PipfileLock::parse(lock_content).map_err(|e| {
+            let diagnostic = Diagnostic::from_json_error(&e, lock_content);
+            ConversionError::input(PIPFILE_LOCK, lock_content, vec![diagnostic])
+        })?;
+
+        // a Pipfile with syntax errors only gets here in best-effort mode
+        let is_up_to_date = pipfile_lock.is_up_to_date(pipfile).unwrap_or(false);
+        if !is_up_to_date && file_content.options.strict_lock {
+            return Err(ConversionError::StaleLock);
+        }
+        if !is_up_to_date {
+            diagnostics.push(Diagnostic::warning(
+                "Pipfile.lock is out of date, its pins may not match the Pipfile".to_string(),
+            ));
+        }
+        lock = Some(pipfile_lock);
+    }
+
+    if let (Some(lock), Some(lock_policy)) = (&lock, file_content.options.lock_policy) {
+        let pinned = lock.pin_packages(&mut file_content.packages, lock_policy, &mut diagnostics);
+        diagnostics.push(Diagnostic::info(format!(
+            "{} unpinned packages are bound from Pipfile.lock",
+            pinned
+        )));
+    }
+
+    if let (Some(lock), true) = (&lock, file_content.options.uv_lock) {
+        // the exact pins cover the transitive packages as well
+        file_content.constraints = lock.exact_constraints(&mut diagnostics);
+        let pinned = lock.pin_git_sources(&mut file_content.packages);
+        diagnostics.push(Diagnostic::info(format!(
+            "{} locked packages are constrained to their exact versions and {} git sources to their locked commits",
+            file_content.constraints.len(),
+            pinned
+        )));
+        // uv.lock records requires-python, without one uv resolves for the Python it runs with
+        if file_content.pipenv.requires_python().is_none() {
+            match &lock.python_version {
+                Some(python_version) => {
+                    file_content.pipenv.set_py_version(python_version);
+                    diagnostics.push(Diagnostic::info(format!(
+                        "requires-python is taken from the Python {} of Pipfile.lock",
+                        python_version
+                    )));
+                }
+                None => diagnostics.push(Diagnostic::warning(
+                    "Neither Pipfile nor Pipfile.lock name a Python version, set requires-python before `uv lock`".to_string(),
+                )),
+            }
+        }
+    } else if let (Some(lock), true) = (&lock, file_content.options.lock_constraints) {
+        file_content.constraints = lock.transitive_constraints(&file_content.packages);
+        diagnostics.push(Diagnostic::info(format!(
+            "{} transitive packages are constrained to their locked versions",
+            file_content.constraints.len()
+        )));
+    }
+
+    Ok(Conversion {
+        pyproject: file_content.export(&mut diagnostics),
+        credential_variables: file_content.credential_variables(),
+        scripts_file: file_content.scripts_file(&mut diagnostics),
+        diagnostics,
+    })
+}
+
+/// Checks a uv.lock resolved from a converted project against the Pipfile.lock it came from,
+/// the returned errors are packages that `uv sync` would install differently from Pipenv
+pub fn verify_uv_lock(
+    pipfile_lock: &str,
+    uv_lock: &str,
+) -> Result<Vec<Diagnostic>, ConversionError> {
+    let lock = PipfileLock::parse(pipfile_lock).map_err(|e| {
+        let diagnostic = Diagnostic::from_json_error(&e, pipfile_lock);
+        ConversionError::input(PIPFILE_LOCK, pipfile_lock, vec![diagnostic])
+    })?;
+    let document = toml_edit::Document::parse(uv_lock.to_string())
+        .map_err(|e| ConversionError::input(UV_LOCK, uv_lock, vec![e.into()]))?;
+    Ok(lock.verify_uv_lock(&document))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_convert() {
+        let pipfile = "[packages]\nrequests = \"==2.31.0\"\n\n[dev-packages]\npytest = \"*\"\n";
+
+        let conversion = convert(pipfile, None, ExportOptions::default()).unwrap();
+
+        assert!(conversion
+            .pyproject
+            .contains("dependencies = [\n\t\"requests==2.31.0\",\n]"));
+        assert!(conversion.pyproject.contains("dev = [\n\t\"pytest\",\n]"));
+        assert!(conversion.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn test_convert_with_problems() {
+        let pipfile = "[packages]\nrequests = \"latest\"\nflask = \"*\"\n";
+
+        match convert(pipfile, None, ExportOptions::default()) {
+            Err(ConversionError::Input { diagnostics, .. }) => assert_eq!(diagnostics.len(), 1),
+            _ => panic!("Invalid version was not reported"),
+        }
+
+        let options = ExportOptions {
+            best_effort: true,
+            ..Default::default()
+        };
+        let conversion = convert(pipfile, None, options).unwrap();
+        assert_eq!(conversion.diagnostics.len(), 1);
+        assert!(conversion.diagnostics[0].is_error());
+        assert!(conversion.pyproject.contains("\"flask\""));
+    }
+
+    #[test]
+    fn test_convert_for_uv_lock() {
+        let pipfile = "[packages]\nrequests = \">=2\"\n";
+        let lock = r#"{"_meta": {"requires": {"python_version": "3.11"}}, "default": {
+            "requests": {"hashes": ["sha256:aaa"], "version": "==2.31.0"},
+            "idna": {"hashes": ["sha256:bbb"], "version": "==3.6"}
+        }}"#;
+        let options = ExportOptions {
+            uv_lock: true,
+            ..Default::default()
+        };
+
+        let conversion = convert(pipfile, Some(lock), options).unwrap();
+
+        assert!(conversion.pyproject.contains("\"requests>=2\""));
+        assert!(conversion
+            .pyproject
+            .contains("requires-python = \">=3.11\""));
+        assert!(conversion
+            .pyproject
+            .contains("constraint-dependencies = [\n\t\"idna==3.6\",\n\t\"requests==2.31.0\",\n]"));
+
+        let uv_lock = "version = 1\n\n[[package]]\nname = \"idna\"\nversion = \"3.6\"\nsource = { registry = \"https://pypi.org/simple\" }\n";
+        let diagnostics = verify_uv_lock(lock, uv_lock).unwrap();
+        assert_eq!(diagnostics.len(), 1);
+        assert!(!diagnostics[0].is_error());
+        assert!(verify_uv_lock(lock, "[[package]").is_err());
+
+        let lock = r#"{"default": {}}"#;
+        let options = ExportOptions {
+            uv_lock: true,
+            ..Default::default()
+        };
+        let conversion = convert(pipfile, Some(lock), options).unwrap();
+        assert!(!conversion.pyproject.contains("requires-python"));
+        assert!(conversion
+            .diagnostics
+            .iter()
+            .any(
+                |diagnostic| diagnostic.severity == errors::Severity::Warning
+                    && diagnostic.message.contains("requires-python")
+            ));
+    }
+
+    #[test]
+    fn test_convert_notes() {
+        let pipfile = "[packages]\nrequests = \"*\"\n\n[dev-packages]\nRequests = \"*\"\n";
+        let lock = r#"{"_meta": {"hash": {"sha256": "outdated"}}, "default": {}, "develop": {}}"#;
+        let options = ExportOptions {
+            lock_constraints: true,
+            ..Default::default()
+        };
+
+        let conversion = convert(pipfile, Some(lock), options).unwrap();
+
+        let warnings: Vec<&str> = conversion
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == errors::Severity::Warning)
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect();
+        assert_eq!(
+            warnings,
+            vec![
+                "Package Requests in [dev-packages] duplicates requests in [packages]",
+                "Pipfile.lock is out of date, its pins may not match the Pipfile",
+            ]
+        );
+        assert!(!conversion.diagnostics.iter().any(Diagnostic::is_error));
+    }
+}
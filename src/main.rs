@@ -1,116 +1,258 @@
-mod models;
-mod processors;
+mod cli;
 mod utils;
 
-use crate::models::pipenv::Pipenv;
-use crate::utils::get_output_file_name;
-use models::package::Package;
-use models::pipenv::PipenvRequirements;
-use models::pipenv_content::{PipenvContent, PipenvUVInterface};
-use models::source::Source;
-use processors::BufferResultEnum;
-use std::env;
+use crate::cli::Cli;
+use crate::utils::{get_output_file_name, requested_output_path};
+use clap::Parser;
+use pipenv2uv::errors::{render_report, ConversionError, Diagnostic, Severity};
+use pipenv2uv::log::{enabled, set_verbosity, Verbosity};
+use pipenv2uv::merge::merge_pyproject;
+use pipenv2uv::{
+    convert, info, parse_pipfile, verbose, verify_uv_lock, warning, Conversion, ExportOptions,
+    PIPFILE, PIPFILE_LOCK, UV_LOCK,
+};
 use std::fs::File;
-use std::io::{BufRead, BufReader, Write};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
 
-fn open_file(filename: &str) -> std::io::Result<BufReader<File>> {
-    let file = File::open(filename)?;
-    Ok(BufReader::new(file))
+fn write_file(
+    path: &Path,
+    content: &str,
+    description: &str,
+    dry_run: bool,
+) -> Result<(), ConversionError> {
+    if dry_run {
+        info!("Would save {} to {}", description, path.display());
+        return Ok(());
+    }
+    info!("Saving {} to {}", description, path.display());
+
+    let io_error = |error| ConversionError::io(&path.display().to_string(), error);
+    if let Some(directory) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        std::fs::create_dir_all(directory).map_err(io_error)?;
+    }
+    let mut file: File = File::create(path).map_err(io_error)?;
+    write!(file, "{}", content).map_err(io_error)
 }
 
-fn read_lines(reader: BufReader<File>) -> Result<PipenvContent, std::io::Error> {
-    let mut line_buffer = Vec::<String>::new();
-    let mut block_name = "empty".to_string();
-
-    let mut sources: Vec<Source> = Vec::new();
-    let mut packages: Vec<Package> = Vec::new();
-    let mut pipenv: Pipenv = PipenvRequirements::new();
-
-    for line in reader.lines() {
-        let line = line?;
-
-        if line.starts_with("python_version") {
-            pipenv
-                .set_py_version(&line)
-                .expect("Failed to set parse python version");
-        } else if line.starts_with("allow_prereleases") {
-            pipenv
-                .set_prereleases_status(&line)
-                .expect("Failed to set prereleases status");
-        } else if line.starts_with('[') && line.ends_with(']') {
-            let new_block_name = line
-                .trim_start_matches('[')
-                .trim_end_matches(']')
-                .to_string();
-
-            if !line_buffer.is_empty() {
-                match processors::process_previous_buffer(block_name.as_str(), &line_buffer) {
-                    BufferResultEnum::Source(processed_source) => {
-                        sources.push(processed_source);
-                    }
-                    BufferResultEnum::Packages(processed_packages) => {
-                        packages.extend(processed_packages);
-                    }
-                    _ => {}
-                }
-            }
-            block_name = new_block_name;
-            line_buffer.clear();
-        } else {
-            line_buffer.push(line);
+/// Writes a file next to the output, keeping an existing one unless forced
+fn write_side_file(
+    cli: &Cli,
+    path: &Path,
+    content: &str,
+    description: &str,
+) -> Result<bool, ConversionError> {
+    if path.exists() && !cli.force {
+        info!("File {} already exists, not overwriting it", path.display());
+        return Ok(false);
+    }
+    write_file(path, content, description, cli.dry_run)?;
+    Ok(true)
+}
+
+fn read_file(path: &Path) -> Result<String, ConversionError> {
+    let name: String = path.display().to_string();
+    info!("Reading {}", name);
+    std::fs::read_to_string(path).map_err(|e| ConversionError::io(&name, e))
+}
+
+/// Checks the uv.lock next to the Pipfile against its Pipfile.lock
+fn verify_lock(pipfile_path: &Path) -> Result<(), ConversionError> {
+    let lock_path: PathBuf = pipfile_path.with_file_name(PIPFILE_LOCK);
+    let uv_lock_path: PathBuf = pipfile_path.with_file_name(UV_LOCK);
+    let lock_content: String = read_file(&lock_path)?;
+    let uv_lock_content: String = read_file(&uv_lock_path)?;
+
+    let (problems, notes): (Vec<Diagnostic>, Vec<Diagnostic>) =
+        verify_uv_lock(&lock_content, &uv_lock_content)
+            .map_err(|error| match error {
+                ConversionError::Input {
+                    file_name,
+                    content,
+                    diagnostics,
+                } => ConversionError::Input {
+                    file_name: if file_name == PIPFILE_LOCK {
+                        lock_path.display().to_string()
+                    } else {
+                        uv_lock_path.display().to_string()
+                    },
+                    content,
+                    diagnostics,
+                },
+                error => error,
+            })?
+            .into_iter()
+            .partition(Diagnostic::is_error);
+    for note in notes {
+        warning!("{}", note.message);
+    }
+    if !problems.is_empty() {
+        return Err(ConversionError::input(
+            &uv_lock_path.display().to_string(),
+            &uv_lock_content,
+            problems,
+        ));
+    }
+    info!("uv.lock matches Pipfile.lock, `uv sync --locked` installs the locked versions");
+    Ok(())
+}
+
+fn process_data(cli: &Cli) -> Result<(), ConversionError> {
+    let pipfile_path: PathBuf = if cli.input.is_dir() {
+        cli.input.join(PIPFILE)
+    } else {
+        cli.input.clone()
+    };
+    if cli.verify_uv_lock {
+        return verify_lock(&pipfile_path);
+    }
+    let lock_path: PathBuf = pipfile_path.with_file_name(PIPFILE_LOCK);
+    let pipfile_name: String = pipfile_path.display().to_string();
+    let lock_name: String = lock_path.display().to_string();
+
+    let content: String = read_file(&pipfile_path)?;
+    if enabled(Verbosity::Verbose) {
+        // the library does not print, the entries are listed from a parse of their own
+        let (parsed, _) = parse_pipfile(&content, ExportOptions::default());
+        for package in &parsed.packages {
+            verbose!("Found package {} in [{}]", package.name, package.category);
+        }
+        for script in &parsed.scripts {
+            verbose!("Found script {}", script.name);
         }
     }
-    match processors::process_previous_buffer(block_name.as_str(), &line_buffer) {
-        BufferResultEnum::Source(processed_source) => {
-            sources.push(processed_source);
+
+    let mut lock_content: Option<String> = None;
+    if cli.uses_lock() {
+        if lock_path.exists() {
+            lock_content = Some(read_file(&lock_path)?);
+        } else {
+            info!("Pipfile.lock is not found, versions are kept as in Pipfile");
         }
-        BufferResultEnum::Packages(processed_packages) => {
-            packages.extend(processed_packages);
+    }
+
+    let project_directory: &Path = pipfile_path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let options = cli.export_options(project_directory)?;
+    verbose!("Project name {}", options.project.name);
+
+    let mut conversion: Conversion =
+        convert(&content, lock_content.as_deref(), options).map_err(|error| match error {
+            // the library only knows the default file names
+            ConversionError::Input {
+                file_name,
+                content,
+                diagnostics,
+            } => ConversionError::Input {
+                file_name: if file_name == PIPFILE_LOCK {
+                    lock_name.clone()
+                } else {
+                    pipfile_name.clone()
+                },
+                content,
+                diagnostics,
+            },
+            error => error,
+        })?;
+    let (problems, notes): (Vec<Diagnostic>, Vec<Diagnostic>) =
+        std::mem::take(&mut conversion.diagnostics)
+            .into_iter()
+            .partition(Diagnostic::is_error);
+    for note in notes {
+        match note.severity {
+            Severity::Warning => warning!("{}", note.message),
+            _ => info!("{}", note.message),
         }
-        _ => {}
     }
-    Ok(PipenvContent {
-        sources,
-        packages,
-        pipenv,
-    })
-}
+    if !problems.is_empty() {
+        // entries left out with --best-effort are errors, printed even with --quiet
+        eprintln!(
+            "error: {}",
+            render_report(&pipfile_name, &content, &problems, "error")
+        );
+        info!("Broken entries are left out, the output is incomplete");
+    }
 
-fn create_file_for_writing(filename: &str) -> File {
-    let current_dir = env::current_dir().expect("Failed to get current directory");
-    let full_path = current_dir.join(filename).to_str().unwrap().to_string();
-    File::create(filename).unwrap_or_else(|_| {
-        panic!(
-            "Unable to create new file in current directory: {}",
-            full_path
-        )
-    })
-}
+    let requested_path: PathBuf = requested_output_path(cli.output.as_deref(), &pipfile_path);
+    let merge_target: bool = cli.merge && requested_path.exists();
+    let pyproject: String = if merge_target {
+        let requested_name: String = requested_path.display().to_string();
+        info!("Merging into {}", requested_name);
+        let existing = std::fs::read_to_string(&requested_path)
+            .map_err(|e| ConversionError::io(&requested_name, e))?;
+        merge_pyproject(&existing, &requested_name, &conversion.pyproject)?
+    } else {
+        conversion.pyproject.clone()
+    };
 
-fn process_data() -> Result<(), std::io::Error> {
-    println!("Reading Pipfile's content");
-    let original_file = "Pipfile";
-    let reader = open_file(original_file).expect("Cannot open Pipfile");
-    let file_content: PipenvContent =
-        read_lines(reader).expect("Error while reading Pipfile content");
+    if cli.stdout {
+        print!("{}", pyproject);
+        if conversion.scripts_file.is_some() || cli.write_env_example {
+            warning!(".env.example and script files are not written with --stdout");
+        }
+        return Ok(());
+    }
 
-    let exported_lines: String = file_content.export();
+    // a merged file replaces the one it was read from
+    let result_path: PathBuf = if merge_target {
+        requested_path
+    } else {
+        get_output_file_name(&requested_path, cli.force)
+    };
 
-    let is_docker = env::var("DOCKER").unwrap_or("0".to_string()) == "1";
-    let result_filename = get_output_file_name(is_docker);
+    write_file(&result_path, &pyproject, "processed data", cli.dry_run)?;
 
-    println!("Saving processed data to {}", result_filename);
+    if cli.uv_lock && lock_content.is_some() {
+        info!("Run `uv lock`, then check the result with `--verify-uv-lock` before `uv sync --locked`");
+    }
 
-    let mut export_file: File = create_file_for_writing(&result_filename);
+    if let (Some(scripts_file), Some(file_name)) =
+        (&conversion.scripts_file, cli.scripts.file_name())
+    {
+        let scripts_path = result_path.with_file_name(file_name);
+        write_side_file(cli, &scripts_path, scripts_file, "Pipfile scripts")?;
+    }
 
-    write!(export_file, "{}", exported_lines).expect("Writing to file failed");
+    if cli.write_env_example && !conversion.credential_variables.is_empty() {
+        let env_example_path = result_path.with_file_name(".env.example");
+        let env_example: String = conversion
+            .credential_variables
+            .iter()
+            .map(|(variable, value)| format!("{}={}\n", variable, value))
+            .collect();
+        write_side_file(
+            cli,
+            &env_example_path,
+            &env_example,
+            "index credentials template",
+        )?;
+    }
 
     Ok(())
 }
 
-fn main() {
-    match process_data() {
-        Ok(_) => println!("Processing completed successfully"),
-        Err(e) => eprintln!("Error: {}", e),
+fn main() -> ExitCode {
+    let cli: Cli = Cli::parse();
+    if cli.quiet {
+        set_verbosity(Verbosity::Quiet);
+    } else if cli.verbose {
+        set_verbosity(Verbosity::Verbose);
+    }
+
+    match process_data(&cli) {
+        Ok(_) => {
+            info!("Processing completed successfully");
+            ExitCode::SUCCESS
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
     }
 }
@@ -0,0 +1,622 @@
+use crate::errors::ConversionError;
+use crate::models::package::canonicalize_name;
+use toml_edit::{Array, Decor, DocumentMut, Item, Table, TableLike, TomlError, Value};
+
+const GENERATED_FILE_NAME: &str = "generated pyproject.toml";
+
+/// Positions of every table in the subtree, tables created by hand have none
+fn collect_positions(item: &Item, positions: &mut Vec<isize>) {
+    match item {
+        Item::Table(table) => {
+            positions.extend(table.position());
+            for (_, item) in table.iter() {
+                collect_positions(item, positions);
+            }
+        }
+        Item::ArrayOfTables(tables) => {
+            for table in tables.iter() {
+                positions.extend(table.position());
+                for (_, item) in table.iter() {
+                    collect_positions(item, positions);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Copies a table from the generated document, without the positions it had there
+fn detached(item: &Item) -> Item {
+    let mut item: Item = item.clone();
+    clear_positions(&mut item);
+    item
+}
+
+fn clear_positions(item: &mut Item) {
+    match item {
+        Item::Table(table) => {
+            table.set_position(None);
+            for (_, item) in table.iter_mut() {
+                clear_positions(item);
+            }
+        }
+        Item::ArrayOfTables(tables) => {
+            for table in tables.iter_mut() {
+                table.set_position(None);
+                for (_, item) in table.iter_mut() {
+                    clear_positions(item);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Puts a blank line before the table header at a position and returns what was before it,
+/// the comments at the top of the file belong to the first table
+fn take_table_prefix(item: &mut Item, position: isize) -> Option<String> {
+    let table: &mut Table = item.as_table_mut()?;
+    if table.position() == Some(position) && !table.is_implicit() {
+        let prefix: Option<String> = table
+            .decor()
+            .prefix()
+            .and_then(|prefix| prefix.as_str())
+            .map(String::from);
+        table.decor_mut().set_prefix("\n");
+        return prefix;
+    }
+    table
+        .iter_mut()
+        .find_map(|(_, item)| take_table_prefix(item, position))
+}
+
+/// Replaces the value of a key, keeping the key where and how it was written
+fn set_item(table: &mut dyn TableLike, key: &str, item: Item) {
+    match table.get_mut(key) {
+        Some(existing) => *existing = item,
+        None => {
+            table.insert(key, item);
+        }
+    }
+}
+
+/// Canonical package name at the start of a PEP 508 requirement
+fn requirement_name(requirement: &str) -> String {
+    let requirement: &str = requirement.trim();
+    let end: usize = requirement
+        .find(|character: char| !(character.is_ascii_alphanumeric() || "-_.".contains(character)))
+        .unwrap_or(requirement.len());
+    canonicalize_name(&requirement[..end])
+}
+
+/// Appends a requirement indented like the last one, its comment is not copied
+fn push_requirement(existing: &mut Array, requirement: &str) {
+    let indentation: Option<String> = existing.iter().last().and_then(|last| {
+        let prefix: &str = last.decor().prefix()?.as_str()?;
+        let (_, indentation) = prefix.rsplit_once('\n')?;
+        Some(format!("\n{}", indentation))
+    });
+    match indentation {
+        Some(indentation) => {
+            let mut value: Value = Value::from(requirement);
+            value.decor_mut().set_prefix(indentation);
+            value.decor_mut().set_suffix("");
+            existing.push_formatted(value);
+        }
+        None => existing.push(requirement),
+    }
+}
+
+/// Updates requirements of the same package in place and appends new ones,
+/// requirements only known to the existing array are kept with their comments
+fn merge_requirements(existing: &mut Array, generated: &Array) {
+    let mut names: Vec<String> = Vec::new();
+    for requirement in generated.iter().filter_map(Value::as_str) {
+        let name: String = requirement_name(requirement);
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+
+    for name in names {
+        let requirements: Vec<&str> = generated
+            .iter()
+            .filter_map(Value::as_str)
+            .filter(|requirement| requirement_name(requirement) == name)
+            .collect();
+        let positions: Vec<usize> = existing
+            .iter()
+            .enumerate()
+            .filter(|(_, value)| {
+                value
+                    .as_str()
+                    .is_some_and(|value| requirement_name(value) == name)
+            })
+            .map(|(position, _)| position)
+            .collect();
+
+        // a package listed several times, e.g. once per python_version marker, cannot be
+        // matched entry by entry, so all its entries are replaced where the first one was
+        let decors: Vec<Decor> = positions
+            .iter()
+            .filter_map(|position| existing.get(*position))
+            .map(|value| value.decor().clone())
+            .collect();
+        for position in positions.iter().rev() {
+            existing.remove(*position);
+        }
+        let Some(first_position) = positions.first() else {
+            for requirement in requirements {
+                push_requirement(existing, requirement);
+            }
+            continue;
+        };
+        for (offset, requirement) in requirements.into_iter().enumerate() {
+            let mut value: Value = Value::from(requirement);
+            match decors.get(offset) {
+                Some(decor) => *value.decor_mut() = decor.clone(),
+                None => {
+                    // further entries are indented like the last replaced one, without its comment
+                    let last: Option<&Decor> = decors.last();
+                    value.decor_mut().set_prefix(
+                        last.and_then(|decor| decor.prefix())
+                            .and_then(|prefix| prefix.as_str())
+                            .and_then(|prefix| prefix.rsplit_once('\n'))
+                            .map(|(_, indentation)| format!("\n{}", indentation))
+                            .unwrap_or(" ".to_string()),
+                    );
+                    value.decor_mut().set_suffix("");
+                }
+            }
+            existing.insert_formatted(first_position + offset, value);
+        }
+    }
+}
+
+/// Merges a generated requirement list into the array stored under a key
+fn merge_requirement_list(table: &mut dyn TableLike, key: &str, generated: &Item) {
+    match (
+        table.get_mut(key).and_then(|item| item.as_array_mut()),
+        generated.as_array(),
+    ) {
+        (Some(existing), Some(generated)) => merge_requirements(existing, generated),
+        _ => set_item(table, key, generated.clone()),
+    }
+}
+
+fn merge_project(document: &mut DocumentMut, generated_project: &Table) {
+    let Some(project) = document
+        .get_mut("project")
+        .and_then(|item| item.as_table_like_mut())
+    else {
+        // a new [project] goes before every existing table
+        let mut positions: Vec<isize> = Vec::new();
+        collect_positions(document.as_item(), &mut positions);
+        let first_position: isize = positions.into_iter().min().unwrap_or(0);
+        let prefix: Option<String> = take_table_prefix(document.as_item_mut(), first_position);
+        let mut project: Item = detached(&Item::Table(generated_project.clone()));
+        if let Some(table) = project.as_table_mut() {
+            table.set_position(Some(first_position - 1));
+            if let Some(prefix) = prefix {
+                table.decor_mut().set_prefix(prefix);
+            }
+        }
+        document.insert("project", project);
+        return;
+    };
+
+    if let Some(dependencies) = generated_project.get("dependencies") {
+        merge_requirement_list(project, "dependencies", dependencies);
+    }
+    if let Some(generated_scripts) = generated_project.get("scripts") {
+        merge_entries(project, "scripts", generated_scripts);
+    }
+    let requires_python = generated_project
+        .get("requires-python")
+        .filter(|item| !item.as_str().unwrap_or_default().is_empty());
+    if let (Some(requires_python), false) =
+        (requires_python, project.contains_key("requires-python"))
+    {
+        project.insert("requires-python", requires_python.clone());
+    }
+}
+
+fn merge_dependency_groups(document: &mut DocumentMut, generated_groups: &Table) {
+    match document
+        .get_mut("dependency-groups")
+        .and_then(|item| item.as_table_like_mut())
+    {
+        Some(groups) => {
+            for (group_name, dependencies) in generated_groups.iter() {
+                merge_requirement_list(groups, group_name, dependencies);
+            }
+        }
+        None => {
+            // keep the groups next to the project dependencies
+            let mut positions: Vec<isize> = Vec::new();
+            if let Some(project) = document.get("project") {
+                collect_positions(project, &mut positions);
+            }
+            let mut groups: Item = detached(&Item::Table(generated_groups.clone()));
+            if let (Some(table), Some(position)) =
+                (groups.as_table_mut(), positions.into_iter().max())
+            {
+                table.set_position(Some(position));
+            }
+            document.insert("dependency-groups", groups);
+        }
+    }
+}
+
+/// Sets every entry of a generated table, entries only known to the existing table are kept
+fn merge_entries(parent: &mut dyn TableLike, key: &str, generated: &Item) {
+    match parent
+        .get_mut(key)
+        .and_then(|item| item.as_table_like_mut())
+    {
+        Some(existing) => {
+            for (entry_key, entry) in generated.as_table_like().into_iter().flat_map(|t| t.iter()) {
+                set_item(existing, entry_key, entry.clone());
+            }
+        }
+        None => set_item(parent, key, detached(generated)),
+    }
+}
+
+/// Indexes are matched by name, indexes only known to the existing file are kept
+fn merge_indexes(uv: &mut dyn TableLike, generated_indexes: &Item) {
+    let Some(generated_indexes) = generated_indexes.as_array_of_tables() else {
+        return;
+    };
+    let index_name = |index: &dyn TableLike| -> Option<String> {
+        index
+            .get("name")
+            .and_then(|item| item.as_str())
+            .map(String::from)
+    };
+
+    match uv.get_mut("index") {
+        None => set_item(
+            uv,
+            "index",
+            detached(&Item::ArrayOfTables(generated_indexes.clone())),
+        ),
+        Some(Item::ArrayOfTables(indexes)) => {
+            for generated_index in generated_indexes.iter() {
+                let name: Option<String> = index_name(generated_index);
+                let existing_index = indexes.iter_mut().find(|index| index_name(*index) == name);
+                match existing_index {
+                    Some(index) => {
+                        for (key, item) in generated_index.iter() {
+                            set_item(index, key, item.clone());
+                        }
+                    }
+                    None => {
+                        let mut index: Table = generated_index.clone();
+                        index.set_position(None);
+                        indexes.push(index);
+                    }
+                }
+            }
+        }
+        // `index = [{name = "corp", url = "..."}]`, new indexes are written the same way
+        Some(Item::Value(Value::Array(indexes))) => {
+            for generated_index in generated_indexes.iter() {
+                let name: Option<String> = index_name(generated_index);
+                let existing_index = indexes
+                    .iter_mut()
+                    .filter_map(Value::as_inline_table_mut)
+                    .find(|index| index_name(*index) == name);
+                match existing_index {
+                    Some(index) => {
+                        for (key, item) in generated_index.iter() {
+                            set_item(index, key, item.clone());
+                        }
+                    }
+                    None => indexes.push(generated_index.clone().into_inline_table()),
+                }
+            }
+        }
+        // anything else is not a valid index list, uv reports it and it is left to the user
+        Some(_) => {}
+    }
+}
+
+/// `[tool]` of the document, created implicit so that only its subtables get a header
+fn tool_table(document: &mut DocumentMut) -> Option<&mut dyn TableLike> {
+    document
+        .entry("tool")
+        .or_insert_with(|| {
+            let mut tool: Table = Table::new();
+            tool.set_implicit(true);
+            Item::Table(tool)
+        })
+        .as_table_like_mut()
+}
+
+fn merge_tool_uv(document: &mut DocumentMut, generated_uv: &Table) {
+    let Some(tool) = tool_table(document) else {
+        return;
+    };
+    let Some(uv) = tool.get_mut("uv").and_then(|item| item.as_table_like_mut()) else {
+        tool.insert("uv", detached(&Item::Table(generated_uv.clone())));
+        return;
+    };
+
+    for (key, item) in generated_uv.iter() {
+        match key {
+            "index" => merge_indexes(uv, item),
+            "sources" => merge_entries(uv, "sources", item),
+            _ => set_item(uv, key, item.clone()),
+        }
+    }
+}
+
+fn merge_poe(document: &mut DocumentMut, generated_poe: &Table) {
+    let Some(tool) = tool_table(document) else {
+        return;
+    };
+    let Some(poe) = tool
+        .get_mut("poe")
+        .and_then(|item| item.as_table_like_mut())
+    else {
+        tool.insert("poe", detached(&Item::Table(generated_poe.clone())));
+        return;
+    };
+    if let Some(generated_tasks) = generated_poe.get("tasks") {
+        merge_entries(poe, "tasks", generated_tasks);
+    }
+}
+
+/// Adds `[tool.*]` tables of the generated build backend that the existing file lacks
+fn add_backend_settings(document: &mut DocumentMut, generated_tool: &Table) {
+    let Some(tool) = tool_table(document) else {
+        return;
+    };
+    for (key, item) in generated_tool.iter() {
+        if !matches!(key, "uv" | "poe") && !tool.contains_key(key) {
+            tool.insert(key, detached(item));
+        }
+    }
+}
+
+/// Updates an existing pyproject.toml with the converted dependencies and uv settings,
+/// everything else in it is kept as written, comments included
+pub fn merge_pyproject(
+    existing: &str,
+    existing_name: &str,
+    generated: &str,
+) -> Result<String, ConversionError> {
+    let mut document: DocumentMut = existing.parse().map_err(|error: TomlError| {
+        ConversionError::input(existing_name, existing, vec![error.into()])
+    })?;
+    let generated_document: DocumentMut = generated.parse().map_err(|error: TomlError| {
+        ConversionError::input(GENERATED_FILE_NAME, generated, vec![error.into()])
+    })?;
+
+    if let Some(generated_project) = generated_document.get("project").and_then(Item::as_table) {
+        merge_project(&mut document, generated_project);
+    }
+    if let Some(generated_groups) = generated_document
+        .get("dependency-groups")
+        .and_then(Item::as_table)
+    {
+        merge_dependency_groups(&mut document, generated_groups);
+    }
+    if let Some(generated_uv) = generated_document
+        .get("tool")
+        .and_then(|tool| tool.get("uv"))
+        .and_then(Item::as_table)
+    {
+        merge_tool_uv(&mut document, generated_uv);
+    }
+    if let Some(generated_poe) = generated_document
+        .get("tool")
+        .and_then(|tool| tool.get("poe"))
+        .and_then(Item::as_table)
+    {
+        merge_poe(&mut document, generated_poe);
+    }
+    if let (Some(build_system), false) = (
+        generated_document.get("build-system"),
+        document.contains_key("build-system"),
+    ) {
+        let mut positions: Vec<isize> = Vec::new();
+        collect_positions(document.as_item(), &mut positions);
+        let mut build_system: Item = detached(build_system);
+        if let Some(table) = build_system.as_table_mut() {
+            table.decor_mut().set_prefix("\n");
+            // without a position it would be written after whichever table precedes it in the tree
+            table.set_position(Some(positions.into_iter().max().unwrap_or(0) + 1));
+        }
+        document.insert("build-system", build_system);
+
+        // the backend settings only make sense with the backend they were written for
+        if let Some(generated_tool) = generated_document.get("tool").and_then(Item::as_table) {
+            add_backend_settings(&mut document, generated_tool);
+        }
+    }
+
+    Ok(document.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXISTING: &str = r#"# project settings
+[project]
+name = "service"
+description = "Keeps its description"
+dependencies = [
+    "flask",  # web
+    "gunicorn",
+]
+
+[tool.ruff]
+line-length = 100 # kept
+
+[tool.poe.tasks]
+lint = "ruff check ."  # kept
+test = "pytest"
+
+[[tool.uv.index]]
+name = "internal"
+url = "https://old.mirror/simple"
+
+[[tool.uv.index]]
+name = "extra"
+url = "https://extra/simple"
+"#;
+
+    const GENERATED: &str = r#"[project]
+name = "type-your-project-name-here"
+version = "0.1.0"
+requires-python = ">=3.11"
+dependencies = [
+	"flask>=3",
+	"requests",
+]
+
+[dependency-groups]
+dev = [
+	"pytest",
+]
+
+[tool.uv]
+package = true
+
+[[tool.uv.index]]
+name = "internal"
+url = "https://new.mirror/simple"
+explicit = true
+
+[tool.uv.sources]
+requests = {index="internal"}
+
+[tool.poe.tasks]
+test = "pytest -x"
+
+[build-system]
+requires = ["setuptools>=61"]
+build-backend = "setuptools.build_meta"
+
+[tool.setuptools.packages.find]
+where = ["src"]
+"#;
+
+    #[test]
+    fn test_merge_pyproject() {
+        let merged = merge_pyproject(EXISTING, "pyproject.toml", GENERATED).unwrap();
+
+        assert!(merged.starts_with("# project settings\n[project]\nname = \"service\"\n"));
+        assert!(merged.contains("description = \"Keeps its description\"\n"));
+        assert!(merged.contains(
+            "dependencies = [\n    \"flask>=3\",  # web\n    \"gunicorn\",\n    \"requests\",\n]"
+        ));
+        assert!(merged.contains("requires-python = \">=3.11\""));
+        assert!(!merged.contains("version = "));
+        assert!(merged.contains("[tool.ruff]\nline-length = 100 # kept\n"));
+        assert!(merged.contains("url = \"https://new.mirror/simple\"\nexplicit = true"));
+        assert!(merged.contains("name = \"extra\"\nurl = \"https://extra/simple\""));
+        assert!(merged.contains("[tool.uv.sources]\nrequests = {index=\"internal\"}"));
+        assert!(merged
+            .contains("[tool.poe.tasks]\nlint = \"ruff check .\"  # kept\ntest = \"pytest -x\"\n"));
+        assert!(merged.contains("[build-system]"));
+
+        let project_start = merged.find("[project]").unwrap();
+        let groups_start = merged.find("[dependency-groups]").unwrap();
+        let ruff_start = merged.find("[tool.ruff]").unwrap();
+        assert!(project_start < groups_start && groups_start < ruff_start);
+    }
+
+    #[test]
+    fn test_merge_dependency_groups() {
+        let existing = "[dependency-groups]\ndev = [\"Flask_Login>=0.5\", \"black\"]\n";
+        let generated = "[dependency-groups]\ndev = [\n\t\"flask-login>=0.6\",\n\t\"isort\",\n]\n";
+
+        let merged = merge_pyproject(existing, "pyproject.toml", generated).unwrap();
+
+        assert_eq!(
+            merged,
+            "[dependency-groups]\ndev = [\"flask-login>=0.6\", \"black\", \"isort\"]\n"
+        );
+    }
+
+    #[test]
+    fn test_merge_requirements_listed_twice() {
+        let existing = "[project]\ndependencies = [\n    \"numpy<2; python_version < '3.9'\",\n    \"numpy>=2; python_version >= '3.9'\",\n    \"scipy\",\n]\n";
+        let generated = "[project]\ndependencies = [\n\t\"numpy\",\n]\n";
+
+        let merged = merge_pyproject(existing, "pyproject.toml", generated).unwrap();
+
+        assert!(merged.contains("dependencies = [\n    \"numpy\",\n    \"scipy\",\n]\n"));
+    }
+
+    #[test]
+    fn test_merge_requirements_generated_twice() {
+        let existing = "[dependency-groups]\ndev = [\"black\", \"numpy\", \"scipy\"]\n";
+        let generated = "[dependency-groups]\ndev = [\n\t\"numpy<2; python_version < '3.9'\",\n\t\"numpy>=2; python_version >= '3.9'\",\n]\n";
+
+        let merged = merge_pyproject(existing, "pyproject.toml", generated).unwrap();
+
+        assert_eq!(
+            merged,
+            "[dependency-groups]\ndev = [\"black\", \"numpy<2; python_version < '3.9'\", \"numpy>=2; python_version >= '3.9'\", \"scipy\"]\n"
+        );
+    }
+
+    #[test]
+    fn test_merge_inline_indexes() {
+        let existing = "[tool.uv]\nindex = [{name = \"corp\", url = \"https://corp/simple\"}]\n";
+
+        let merged = merge_pyproject(existing, "pyproject.toml", GENERATED).unwrap();
+
+        assert!(merged.contains(
+            "index = [{name = \"corp\", url = \"https://corp/simple\"}, { name = \"internal\", url = \"https://new.mirror/simple\", explicit = true }]\n"
+        ));
+        assert!(!merged.contains("[[tool.uv.index"));
+        assert!(merged.parse::<DocumentMut>().is_ok());
+    }
+
+    #[test]
+    fn test_merge_into_tool_only_pyproject() {
+        let existing = "[tool.black]\nline-length = 88\n";
+
+        let merged = merge_pyproject(existing, "pyproject.toml", GENERATED).unwrap();
+
+        assert!(merged.starts_with("[project]\nname = \"type-your-project-name-here\""));
+        assert!(merged.contains("\n\n[tool.black]\nline-length = 88\n"));
+        assert!(!merged.contains("]\n[tool.black]"));
+        assert!(merged.contains("[tool.uv]\npackage = true"));
+        assert!(merged.contains("[tool.setuptools.packages.find]\nwhere = [\"src\"]\n"));
+    }
+
+    #[test]
+    fn test_merge_keeps_leading_comment() {
+        let existing =
+            "# Formatting settings\n# shared by the team\n\n[tool.black]\nline-length = 88\n";
+
+        let merged = merge_pyproject(existing, "pyproject.toml", GENERATED).unwrap();
+
+        assert!(merged.starts_with(
+            "# Formatting settings\n# shared by the team\n\n[project]\nname = \"type-your-project-name-here\""
+        ));
+        assert!(merged.contains("]\n\n[tool.black]\nline-length = 88\n"));
+        assert!(merged.contains(
+            "\n\n[build-system]\nrequires = [\"setuptools>=61\"]\nbuild-backend = \"setuptools.build_meta\"\n"
+        ));
+        assert!(merged.find("[build-system]").unwrap() > merged.find("[tool.black]").unwrap());
+        assert_eq!(merged.matches("# Formatting settings").count(), 1);
+    }
+
+    #[test]
+    fn test_merge_invalid_pyproject() {
+        match merge_pyproject("[project\n", "pyproject.toml", GENERATED) {
+            Err(ConversionError::Input { file_name, .. }) => {
+                assert_eq!(file_name, "pyproject.toml")
+            }
+            _ => panic!("Invalid pyproject.toml was not reported"),
+        }
+    }
+}
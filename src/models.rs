@@ -1,4 +1,17 @@
-pub(crate) mod package;
-pub(crate) mod pipenv;
-pub(crate) mod pipenv_content;
-pub(crate) mod source;
+pub mod build;
+pub mod lock;
+pub mod options;
+pub mod package;
+pub mod pipenv;
+pub mod pipenv_content;
+pub mod project;
+pub mod script;
+pub mod source;
+#[cfg(test)]
+pub mod testing;
+pub mod version;
+
+/// Quoted and escaped TOML string, as toml_edit writes it
+pub fn toml_string(value: &str) -> String {
+    toml_edit::Value::from(value).to_string()
+}
@@ -0,0 +1,251 @@
+use crate::models::options::BuildBackend;
+use crate::models::project::{PackageLayout, ProjectMetadata, ProjectVersion};
+use crate::models::toml_string;
+
+fn toml_array(values: &[String]) -> String {
+    let values: Vec<String> = values.iter().map(|value| toml_string(value)).collect();
+    format!("[{}]", values.join(", "))
+}
+
+/// Whether the backend can produce the project version
+pub fn supports_version(backend: BuildBackend, project: &ProjectMetadata) -> bool {
+    // hatchling and flit only read `__version__` of the package itself
+    let package_attribute = |attribute: &str| {
+        project
+            .layout
+            .as_ref()
+            .and_then(|layout| layout.package())
+            .is_some_and(|package| attribute == format!("{}.__version__", package))
+    };
+    match (backend, &project.version) {
+        (_, ProjectVersion::Static(_)) => true,
+        (BuildBackend::Setuptools, _) => true,
+        (BuildBackend::Hatchling, ProjectVersion::Scm) => true,
+        (BuildBackend::Hatchling | BuildBackend::Flit, ProjectVersion::Attribute(attribute)) => {
+            package_attribute(attribute)
+        }
+        _ => false,
+    }
+}
+
+/// Whether the backend can find the packages of the layout
+pub fn supports_layout(backend: BuildBackend, project: &ProjectMetadata) -> bool {
+    // uv_build and flit need the module name, setup.py lists packages only setuptools reads
+    !matches!(
+        (backend, &project.layout),
+        (
+            BuildBackend::UvBuild | BuildBackend::Flit,
+            Some(PackageLayout::SetupScript)
+        )
+    )
+}
+
+fn requirements(backend: BuildBackend, project: &ProjectMetadata) -> Vec<String> {
+    let scm: bool = project.version == ProjectVersion::Scm;
+    // SPDX license expressions are only understood by recent backend versions
+    let license: bool = project.license.is_some();
+    let mut requirements: Vec<&str> = match backend {
+        BuildBackend::Setuptools if license => vec!["setuptools>=77"],
+        BuildBackend::Setuptools if scm => vec!["setuptools>=64"],
+        BuildBackend::Setuptools => vec!["setuptools>=61"],
+        BuildBackend::Hatchling if license => vec!["hatchling>=1.27"],
+        BuildBackend::Hatchling => vec!["hatchling"],
+        BuildBackend::UvBuild => vec!["uv_build>=0.9.0,<0.10.0"],
+        BuildBackend::Flit if license => vec!["flit_core>=3.12,<4"],
+        BuildBackend::Flit => vec!["flit_core>=3.4,<4"],
+    };
+    match (backend, scm) {
+        (BuildBackend::Setuptools, true) => requirements.push("setuptools-scm>=8"),
+        (BuildBackend::Hatchling, true) => requirements.push("hatch-vcs"),
+        _ => {}
+    }
+    requirements.into_iter().map(String::from).collect()
+}
+
+fn setuptools_settings(project: &ProjectMetadata) -> String {
+    let mut resulting_lines: String = String::new();
+
+    match &project.layout {
+        Some(PackageLayout::Src(_)) => {
+            resulting_lines.push_str("\n[tool.setuptools.packages.find]\nwhere = [\"src\"]\n")
+        }
+        // automatic discovery refuses flat layouts with other directories next to the package
+        Some(PackageLayout::Flat(package)) => resulting_lines.push_str(&format!(
+            "\n[tool.setuptools.packages.find]\ninclude = {}\n",
+            toml_array(&[package.clone(), format!("{}.*", package)])
+        )),
+        _ => {}
+    }
+
+    match &project.version {
+        ProjectVersion::Static(_) => {}
+        ProjectVersion::Attribute(attribute) => resulting_lines.push_str(&format!(
+            "\n[tool.setuptools.dynamic]\nversion = {{attr = {}}}\n",
+            toml_string(attribute)
+        )),
+        ProjectVersion::File(file) => resulting_lines.push_str(&format!(
+            "\n[tool.setuptools.dynamic]\nversion = {{file = {}}}\n",
+            toml_string(file)
+        )),
+        ProjectVersion::Scm => resulting_lines.push_str("\n[tool.setuptools_scm]\n"),
+    }
+    resulting_lines
+}
+
+fn hatchling_settings(project: &ProjectMetadata) -> String {
+    let mut resulting_lines: String = String::new();
+    let package_path: Option<String> = project.layout.as_ref().and_then(|l| l.package_path());
+
+    match (&project.version, &package_path) {
+        (ProjectVersion::Scm, _) => {
+            resulting_lines.push_str("\n[tool.hatch.version]\nsource = \"vcs\"\n")
+        }
+        (ProjectVersion::Attribute(_), Some(package_path)) => resulting_lines.push_str(&format!(
+            "\n[tool.hatch.version]\npath = {}\n",
+            toml_string(&format!("{}/__init__.py", package_path))
+        )),
+        _ => {}
+    }
+    if let Some(package_path) = package_path {
+        resulting_lines.push_str(&format!(
+            "\n[tool.hatch.build.targets.wheel]\npackages = {}\n",
+            toml_array(&[package_path])
+        ));
+    }
+    resulting_lines
+}
+
+fn uv_build_settings(project: &ProjectMetadata) -> String {
+    let Some(layout) = &project.layout else {
+        return String::new();
+    };
+    let Some(package) = layout.package() else {
+        return String::new();
+    };
+
+    let mut resulting_lines: String = format!(
+        "\n[tool.uv.build-backend]\nmodule-name = {}\n",
+        toml_string(package)
+    );
+    if let PackageLayout::Flat(_) = layout {
+        resulting_lines.push_str("module-root = \"\"\n");
+    }
+    resulting_lines
+}
+
+fn flit_settings(project: &ProjectMetadata) -> String {
+    // flit finds both layouts by itself, but only a module named after the project
+    match project.layout.as_ref().and_then(|layout| layout.package()) {
+        Some(package) => format!("\n[tool.flit.module]\nname = {}\n", toml_string(package)),
+        None => String::new(),
+    }
+}
+
+/// `[build-system]` and the settings the backend needs to find the package and its version
+pub fn build_system(backend: BuildBackend, project: &ProjectMetadata) -> String {
+    let build_backend: &str = match backend {
+        BuildBackend::Setuptools => "setuptools.build_meta",
+        BuildBackend::Hatchling => "hatchling.build",
+        BuildBackend::UvBuild => "uv_build",
+        BuildBackend::Flit => "flit_core.buildapi",
+    };
+    let mut resulting_lines: String = format!(
+        "[build-system]\nrequires = {}\nbuild-backend = {}\n",
+        toml_array(&requirements(backend, project)),
+        toml_string(build_backend)
+    );
+
+    let settings: String = match backend {
+        BuildBackend::Setuptools => setuptools_settings(project),
+        BuildBackend::Hatchling => hatchling_settings(project),
+        BuildBackend::UvBuild => uv_build_settings(project),
+        BuildBackend::Flit => flit_settings(project),
+    };
+    resulting_lines.push_str(&settings);
+    resulting_lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(version: ProjectVersion, layout: PackageLayout) -> ProjectMetadata {
+        ProjectMetadata {
+            version,
+            layout: Some(layout),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_setuptools_build_system() {
+        let flat = project(
+            ProjectVersion::Attribute("service.__version__".to_string()),
+            PackageLayout::Flat("service".to_string()),
+        );
+
+        assert_eq!(
+            build_system(BuildBackend::Setuptools, &flat),
+            "[build-system]\nrequires = [\"setuptools>=61\"]\nbuild-backend = \"setuptools.build_meta\"\n\n[tool.setuptools.packages.find]\ninclude = [\"service\", \"service.*\"]\n\n[tool.setuptools.dynamic]\nversion = {attr = \"service.__version__\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_other_build_systems() {
+        let src = project(
+            ProjectVersion::Scm,
+            PackageLayout::Src("service".to_string()),
+        );
+        let hatchling = build_system(BuildBackend::Hatchling, &src);
+        assert!(hatchling.contains("requires = [\"hatchling\", \"hatch-vcs\"]\n"));
+        assert!(hatchling.contains("[tool.hatch.version]\nsource = \"vcs\"\n"));
+        assert!(
+            hatchling.contains("[tool.hatch.build.targets.wheel]\npackages = [\"src/service\"]\n")
+        );
+
+        let flat = project(
+            ProjectVersion::Static("1.0".to_string()),
+            PackageLayout::Flat("service".to_string()),
+        );
+        assert!(build_system(BuildBackend::UvBuild, &flat)
+            .ends_with("[tool.uv.build-backend]\nmodule-name = \"service\"\nmodule-root = \"\"\n"));
+        assert!(build_system(BuildBackend::Flit, &flat)
+            .ends_with("[tool.flit.module]\nname = \"service\"\n"));
+    }
+
+    #[test]
+    fn test_supports_version() {
+        let src = project(
+            ProjectVersion::Scm,
+            PackageLayout::Src("service".to_string()),
+        );
+        assert!(supports_version(BuildBackend::Hatchling, &src));
+        assert!(!supports_version(BuildBackend::Flit, &src));
+        assert!(!supports_version(BuildBackend::UvBuild, &src));
+
+        let attribute = project(
+            ProjectVersion::Attribute("service.version.VERSION".to_string()),
+            PackageLayout::Src("service".to_string()),
+        );
+        assert!(!supports_version(BuildBackend::Flit, &attribute));
+        assert!(supports_version(BuildBackend::Setuptools, &attribute));
+    }
+
+    #[test]
+    fn test_supports_layout() {
+        let setup_script = project(
+            ProjectVersion::Static("1.0".to_string()),
+            PackageLayout::SetupScript,
+        );
+        assert!(!supports_layout(BuildBackend::UvBuild, &setup_script));
+        assert!(!supports_layout(BuildBackend::Flit, &setup_script));
+        assert!(supports_layout(BuildBackend::Hatchling, &setup_script));
+        assert!(supports_layout(BuildBackend::Setuptools, &setup_script));
+
+        let src = project(
+            ProjectVersion::Static("1.0".to_string()),
+            PackageLayout::Src("service".to_string()),
+        );
+        assert!(supports_layout(BuildBackend::UvBuild, &src));
+    }
+}
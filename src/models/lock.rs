@@ -0,0 +1,773 @@
+use crate::errors::Diagnostic;
+use crate::models::options::LockPolicy;
+use crate::models::package::{
+    canonicalize_name, GitReference, Package, PackageSource, DEFAULT_CATEGORY,
+};
+use crate::models::version::{Operator, Version, VersionSpecifier, VersionSpecifiers};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use toml_edit::Item;
+
+pub struct LockedPackage {
+    pub name: String,
+    pub version: Option<Version>,
+    pub index: Option<String>,
+    pub markers: Option<String>,
+    // `sha256:...` of every distribution file Pipenv accepted for the version
+    pub hashes: Vec<String>,
+    // commit of a git package
+    pub reference: Option<String>,
+    // git, path and file packages, they do not come from an index
+    pub direct: bool,
+}
+
+/// Resolved packages from Pipfile.lock, grouped by lock section (`default`, `develop`, ...)
+pub struct PipfileLock {
+    // `_meta.hash.sha256`, the hash of the Pipfile the lock was created from
+    pub pipfile_hash: Option<String>,
+    // `_meta.requires`, the Python version the lock was created for
+    pub python_version: Option<String>,
+    pub sections: Vec<(String, Vec<LockedPackage>)>,
+}
+
+/// Pipfile tables that are not hashed as package categories
+const PIPFILE_SECTIONS: [&str; 9] = [
+    "source",
+    "packages",
+    "dev-packages",
+    "requires",
+    "scripts",
+    "pipfile",
+    "pipenv",
+    "default",
+    "develop",
+];
+
+// Pipenv hashes Pipfiles without sources as if they listed PyPI
+const DEFAULT_SOURCE: &str = r#"[[source]]
+name = "pypi"
+url = "https://pypi.org/simple"
+verify_ssl = true
+"#;
+
+fn toml_to_json(value: &toml_edit::Value) -> Value {
+    match value {
+        toml_edit::Value::String(value) => Value::from(value.value().as_str()),
+        toml_edit::Value::Integer(value) => Value::from(*value.value()),
+        toml_edit::Value::Float(value) => Value::from(*value.value()),
+        toml_edit::Value::Boolean(value) => Value::from(*value.value()),
+        toml_edit::Value::Datetime(value) => Value::from(value.value().to_string()),
+        toml_edit::Value::Array(array) => Value::Array(array.iter().map(toml_to_json).collect()),
+        toml_edit::Value::InlineTable(table) => Value::Object(
+            table
+                .iter()
+                .map(|(key, value)| (key.to_string(), toml_to_json(value)))
+                .collect(),
+        ),
+    }
+}
+
+fn item_to_json(item: &toml_edit::Item) -> Value {
+    match item {
+        toml_edit::Item::None => Value::Null,
+        toml_edit::Item::Value(value) => toml_to_json(value),
+        toml_edit::Item::Table(table) => Value::Object(
+            table
+                .iter()
+                .map(|(key, item)| (key.to_string(), item_to_json(item)))
+                .collect(),
+        ),
+        toml_edit::Item::ArrayOfTables(tables) => Value::Array(
+            tables
+                .iter()
+                .map(|table| item_to_json(&toml_edit::Item::Table(table.clone())))
+                .collect(),
+        ),
+    }
+}
+
+/// Escapes non-ASCII characters the way Python's `json.dumps` does by default
+fn ensure_ascii(json: &str) -> String {
+    let mut escaped: String = String::with_capacity(json.len());
+    for character in json.chars() {
+        if character.is_ascii() {
+            escaped.push(character);
+            continue;
+        }
+        let mut units = [0u16; 2];
+        for unit in character.encode_utf16(&mut units) {
+            escaped.push_str(&format!("\\u{:04x}", unit));
+        }
+    }
+    escaped
+}
+
+/// sha256 Pipenv records in Pipfile.lock for this Pipfile content
+pub fn pipfile_hash(pipfile_content: &str) -> Result<String, toml_edit::TomlError> {
+    let mut document: toml_edit::DocumentMut = pipfile_content.parse()?;
+    if !document.contains_key("source") {
+        let separator: &str = if pipfile_content.starts_with('\n') {
+            ""
+        } else {
+            "\n"
+        };
+        document = format!("{}{}{}", DEFAULT_SOURCE, separator, pipfile_content).parse()?;
+    }
+
+    let section = |name: &str| {
+        document
+            .get(name)
+            .map(item_to_json)
+            .unwrap_or(Value::Object(Default::default()))
+    };
+
+    let mut data = serde_json::Map::new();
+    let mut meta = serde_json::Map::new();
+    meta.insert("sources".to_string(), section("source"));
+    meta.insert("requires".to_string(), section("requires"));
+    data.insert("_meta".to_string(), Value::Object(meta));
+    data.insert("default".to_string(), section("packages"));
+    data.insert("develop".to_string(), section("dev-packages"));
+    for (category, item) in document.iter() {
+        if PIPFILE_SECTIONS.contains(&category) {
+            continue;
+        }
+        data.insert(category.to_string(), item_to_json(item));
+    }
+
+    // serde_json keeps object keys sorted, which matches `sort_keys=True`
+    let content: String = ensure_ascii(&Value::Object(data).to_string());
+    let digest = Sha256::digest(content.as_bytes());
+    Ok(digest.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// Pipfile.lock names the default categories differently from the Pipfile
+fn lock_section_name(category: &str) -> &str {
+    match category {
+        DEFAULT_CATEGORY => "default",
+        "dev-packages" => "develop",
+        _ => category,
+    }
+}
+
+fn parse_locked_package(name: &str, data: &Value) -> LockedPackage {
+    let version: Option<Version> = data
+        .get("version")
+        .and_then(|version| version.as_str())
+        .and_then(|version| version.trim_start_matches("==").parse().ok());
+
+    let get_string = |key: &str| {
+        data.get(key)
+            .and_then(|value| value.as_str())
+            .map(|value| value.to_string())
+    };
+
+    let hashes: Vec<String> = data
+        .get("hashes")
+        .and_then(|hashes| hashes.as_array())
+        .map(|hashes| {
+            hashes
+                .iter()
+                .filter_map(|hash| hash.as_str())
+                .map(|hash| hash.to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    LockedPackage {
+        name: name.to_string(),
+        version,
+        index: get_string("index"),
+        markers: get_string("markers"),
+        hashes,
+        reference: get_string("ref").filter(|_| data.get("git").is_some()),
+        direct: ["git", "path", "file"]
+            .iter()
+            .any(|key| data.get(key).is_some()),
+    }
+}
+
+impl PipfileLock {
+    pub fn parse(content: &str) -> Result<Self, serde_json::Error> {
+        let document: Value = serde_json::from_str(content)?;
+
+        let pipfile_hash: Option<String> = document
+            .pointer("/_meta/hash/sha256")
+            .and_then(|hash| hash.as_str())
+            .map(|hash| hash.to_string());
+        let python_version: Option<String> = ["python_version", "python_full_version"]
+            .iter()
+            .find_map(|key| document.pointer(&format!("/_meta/requires/{}", key)))
+            .and_then(|version| version.as_str())
+            .map(|version| version.to_string());
+
+        let mut sections: Vec<(String, Vec<LockedPackage>)> = Vec::new();
+        if let Some(document) = document.as_object() {
+            for (section_name, section) in document {
+                if section_name == "_meta" {
+                    continue;
+                }
+                let Some(section) = section.as_object() else {
+                    continue;
+                };
+                let packages: Vec<LockedPackage> = section
+                    .iter()
+                    .map(|(name, data)| parse_locked_package(name, data))
+                    .collect();
+                sections.push((section_name.clone(), packages));
+            }
+        }
+
+        Ok(PipfileLock {
+            pipfile_hash,
+            python_version,
+            sections,
+        })
+    }
+
+    /// Whether the lock was created from this exact Pipfile content
+    pub fn is_up_to_date(&self, pipfile_content: &str) -> Result<bool, toml_edit::TomlError> {
+        let expected: String = pipfile_hash(pipfile_content)?;
+        Ok(self.pipfile_hash.as_deref() == Some(expected.as_str()))
+    }
+
+    /// Locked version of a package, looking into its own category first
+    pub fn locked_version(&self, category: &str, name: &str) -> Option<&Version> {
+        let canonical_name: String = canonicalize_name(name);
+        let own_section: &str = lock_section_name(category);
+
+        let mut sections: Vec<&(String, Vec<LockedPackage>)> = self.sections.iter().collect();
+        sections.sort_by_key(|(section_name, _)| section_name != own_section);
+
+        sections
+            .into_iter()
+            .flat_map(|(_, packages)| packages)
+            .filter(|package| canonicalize_name(&package.name) == canonical_name)
+            .find_map(|package| package.version.as_ref())
+    }
+
+    /// Replaces `*` specifiers with bounds derived from the locked versions
+    pub fn pin_packages(
+        &self,
+        packages: &mut [Package],
+        policy: LockPolicy,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> usize {
+        let mut pinned: usize = 0;
+        for package in packages.iter_mut() {
+            if !package.version.is_any() || package.source.is_some() {
+                continue;
+            }
+            let Some(version) = self.locked_version(&package.category, &package.name) else {
+                diagnostics.push(Diagnostic::info(format!(
+                    "Package {} is not found in Pipfile.lock",
+                    package.name
+                )));
+                continue;
+            };
+            package.version = specifiers_from_lock(version, policy);
+            pinned += 1;
+        }
+        pinned
+    }
+
+    /// `name==version` pins for locked packages that are not declared in the Pipfile
+    pub fn transitive_constraints(&self, packages: &[Package]) -> Vec<String> {
+        let declared: Vec<String> = packages
+            .iter()
+            .map(|package| canonicalize_name(&package.name))
+            .collect();
+
+        let mut sections: Vec<&(String, Vec<LockedPackage>)> = self.sections.iter().collect();
+        sections.sort_by_key(|(section_name, _)| section_name != "default");
+
+        let mut constrained: Vec<String> = Vec::new();
+        let mut constraints: Vec<String> = Vec::new();
+        for package in sections.into_iter().flat_map(|(_, packages)| packages) {
+            let canonical_name: String = canonicalize_name(&package.name);
+            let Some(version) = &package.version else {
+                continue;
+            };
+            if declared.contains(&canonical_name) || constrained.contains(&canonical_name) {
+                continue;
+            }
+            constraints.push(exact_constraint(&canonical_name, version, package));
+            constrained.push(canonical_name);
+        }
+        constraints
+    }
+
+    /// `name==version` pins for every locked registry package, the declared ones included,
+    /// so that `uv lock` resolves exactly the versions of Pipfile.lock
+    pub fn exact_constraints(&self, diagnostics: &mut Vec<Diagnostic>) -> Vec<String> {
+        // the default section goes first so that its versions win over other categories
+        let mut sections: Vec<&(String, Vec<LockedPackage>)> = self.sections.iter().collect();
+        sections.sort_by_key(|(section_name, _)| section_name != "default");
+
+        let mut constrained: Vec<(String, &Version)> = Vec::new();
+        let mut constraints: Vec<String> = Vec::new();
+        for package in sections.into_iter().flat_map(|(_, packages)| packages) {
+            if package.direct {
+                // their sources are pinned instead, see `pin_git_sources`
+                continue;
+            }
+            let Some(version) = &package.version else {
+                diagnostics.push(Diagnostic::warning(format!(
+                    "Package {} has no locked version and is left for uv to resolve",
+                    package.name
+                )));
+                continue;
+            };
+            let canonical_name: String = canonicalize_name(&package.name);
+            match constrained.iter().find(|(name, _)| *name == canonical_name) {
+                Some((_, existing_version)) if *existing_version != version => {
+                    diagnostics.push(Diagnostic::warning(format!(
+                        "Package {} is locked in several versions, {} is kept",
+                        package.name, existing_version
+                    )));
+                }
+                Some(_) => {}
+                None => {
+                    constraints.push(exact_constraint(&canonical_name, version, package));
+                    constrained.push((canonical_name, version));
+                }
+            }
+        }
+        constraints
+    }
+
+    /// Pins git sources to the commit recorded in Pipfile.lock
+    pub fn pin_git_sources(&self, packages: &mut [Package]) -> usize {
+        let mut pinned: usize = 0;
+        for package in packages.iter_mut() {
+            let canonical_name: String = canonicalize_name(&package.name);
+            let Some(PackageSource::Git { reference, .. }) = &mut package.source else {
+                continue;
+            };
+            let commit: Option<&String> = self
+                .sections
+                .iter()
+                .flat_map(|(_, packages)| packages)
+                .filter(|locked| canonicalize_name(&locked.name) == canonical_name)
+                .find_map(|locked| locked.reference.as_ref());
+            if let Some(commit) = commit {
+                *reference = Some(GitReference::Rev(commit.clone()));
+                pinned += 1;
+            }
+        }
+        pinned
+    }
+
+    /// Checks a uv.lock resolved from the converted project the way `--require-hashes` would:
+    /// every registry package must have its Pipfile.lock version and only distribution files
+    /// whose hash Pipenv recorded
+    pub fn verify_uv_lock(&self, uv_lock: &toml_edit::Document<String>) -> Vec<Diagnostic> {
+        let mut diagnostics: Vec<Diagnostic> = Vec::new();
+        let registry_packages: Vec<&LockedPackage> = self
+            .sections
+            .iter()
+            .flat_map(|(_, packages)| packages)
+            .filter(|package| !package.direct)
+            .collect();
+
+        let mut resolved: Vec<String> = Vec::new();
+        let uv_packages = uv_lock
+            .get("package")
+            .and_then(Item::as_array_of_tables)
+            .into_iter()
+            .flat_map(|packages| packages.iter());
+        for uv_package in uv_packages {
+            // the project itself, git and path packages have no registry source
+            let from_registry: bool = uv_package
+                .get("source")
+                .and_then(Item::as_table_like)
+                .is_some_and(|source| source.contains_key("registry"));
+            let Some(name_item) = uv_package.get("name").filter(|_| from_registry) else {
+                continue;
+            };
+            let name: &str = name_item.as_str().unwrap_or_default();
+            let canonical_name: String = canonicalize_name(name);
+            resolved.push(canonical_name.clone());
+
+            let locked: Vec<&&LockedPackage> = registry_packages
+                .iter()
+                .filter(|package| canonicalize_name(&package.name) == canonical_name)
+                .collect();
+            if locked.is_empty() {
+                diagnostics.push(Diagnostic::new(
+                    &format!("Package {} is not in Pipfile.lock", name),
+                    name_item.span(),
+                ));
+                continue;
+            }
+
+            let version_item: Option<&Item> = uv_package.get("version");
+            let version: Option<Version> = version_item
+                .and_then(Item::as_str)
+                .and_then(|version| version.parse().ok());
+            if !locked
+                .iter()
+                .any(|package| package.version.is_some() && package.version == version)
+            {
+                let locked_versions: Vec<String> = locked
+                    .iter()
+                    .filter_map(|package| package.version.as_ref())
+                    .map(|version| version.to_string())
+                    .collect();
+                diagnostics.push(Diagnostic::new(
+                    &format!(
+                        "Package {} is locked at {} in Pipfile.lock",
+                        name,
+                        locked_versions.join(", ")
+                    ),
+                    version_item.and_then(Item::span).or(name_item.span()),
+                ));
+                continue;
+            }
+
+            let hashes: Vec<&String> = locked.iter().flat_map(|package| &package.hashes).collect();
+            if hashes.is_empty() {
+                diagnostics.push(Diagnostic::warning(format!(
+                    "Package {} has no hashes in Pipfile.lock, its files are not checked",
+                    name
+                )));
+                continue;
+            }
+            let sdist = uv_package
+                .get("sdist")
+                .and_then(Item::as_inline_table)
+                .into_iter();
+            let wheels = uv_package
+                .get("wheels")
+                .and_then(Item::as_array)
+                .into_iter()
+                .flat_map(|wheels| wheels.iter())
+                .filter_map(|wheel| wheel.as_inline_table());
+            for distribution in sdist.chain(wheels) {
+                let Some(hash) = distribution.get("hash") else {
+                    continue;
+                };
+                if !hash
+                    .as_str()
+                    .is_some_and(|hash| hashes.iter().any(|locked| *locked == hash))
+                {
+                    diagnostics.push(Diagnostic::new(
+                        &format!("A file of package {} has a hash not in Pipfile.lock", name),
+                        hash.span(),
+                    ));
+                }
+            }
+        }
+
+        for package in registry_packages {
+            let canonical_name: String = canonicalize_name(&package.name);
+            if !resolved.contains(&canonical_name) {
+                diagnostics.push(Diagnostic::warning(format!(
+                    "Package {} of Pipfile.lock is not in uv.lock",
+                    package.name
+                )));
+                resolved.push(canonical_name);
+            }
+        }
+        diagnostics
+    }
+}
+
+fn exact_constraint(canonical_name: &str, version: &Version, package: &LockedPackage) -> String {
+    let mut constraint: String = format!("{}=={}", canonical_name, version);
+    if let Some(markers) = &package.markers {
+        constraint.push_str("; ");
+        constraint.push_str(markers);
+    }
+    constraint
+}
+
+fn specifiers_from_lock(version: &Version, policy: LockPolicy) -> VersionSpecifiers {
+    let specifier: VersionSpecifier = match policy {
+        LockPolicy::Exact => VersionSpecifier::new(Operator::Equal, version.clone()),
+        LockPolicy::LowerBound => VersionSpecifier::new(
+            Operator::GreaterEqual,
+            Version {
+                local: None,
+                ..version.clone()
+            },
+        ),
+        LockPolicy::Compatible => {
+            // 2.31.0 becomes ~=2.31, which allows any 2.x from 2.31 on,
+            // a pre-release keeps its label so the locked version stays allowed
+            let mut release: Vec<u64> = version.release.clone();
+            if release.len() > 2 {
+                release.pop();
+            }
+            while release.len() < 2 {
+                release.push(0);
+            }
+            VersionSpecifier::new(
+                Operator::Compatible,
+                Version {
+                    epoch: version.epoch,
+                    release,
+                    pre: version.pre,
+                    post: None,
+                    dev: version.dev,
+                    local: None,
+                },
+            )
+        }
+    };
+    VersionSpecifiers(vec![specifier])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::testing::package;
+
+    const LOCK: &str = r#"{
+    "_meta": {"hash": {"sha256": "abc"}, "pipfile-spec": 6},
+    "default": {
+        "requests": {"hashes": [], "index": "pypi", "version": "==2.31.0"},
+        "typing-extensions": {"version": "==4.9.0"}
+    },
+    "develop": {
+        "pytest": {"version": "==8.0.0"},
+        "requests": {"version": "==2.30.0"}
+    }
+}"#;
+
+    #[test]
+    fn test_locked_version() {
+        let lock = PipfileLock::parse(LOCK).unwrap();
+
+        let version = |category: &str, name: &str| {
+            lock.locked_version(category, name)
+                .map(|version| version.to_string())
+        };
+
+        assert_eq!(version("packages", "Requests").unwrap(), "2.31.0");
+        assert_eq!(version("dev-packages", "requests").unwrap(), "2.30.0");
+        assert_eq!(version("packages", "typing_extensions").unwrap(), "4.9.0");
+        assert_eq!(version("packages", "pytest").unwrap(), "8.0.0");
+        assert!(version("packages", "missing").is_none());
+    }
+
+    #[test]
+    fn test_pin_packages() {
+        let lock = PipfileLock::parse(LOCK).unwrap();
+
+        let mut packages = vec![
+            package("requests", "*", "packages"),
+            package("typing-extensions", ">=4", "packages"),
+            package("pytest", "*", "dev-packages"),
+        ];
+
+        let pinned = lock.pin_packages(&mut packages, LockPolicy::Compatible, &mut Vec::new());
+
+        assert_eq!(pinned, 2);
+        assert_eq!(packages[0].version.to_string(), "~=2.31");
+        assert_eq!(packages[1].version.to_string(), ">=4");
+        assert_eq!(packages[2].version.to_string(), "~=8.0");
+
+        let mut packages = vec![package("requests", "*", "packages")];
+        lock.pin_packages(&mut packages, LockPolicy::Exact, &mut Vec::new());
+        assert_eq!(packages[0].version.to_string(), "==2.31.0");
+
+        let mut packages = vec![package("requests", "*", "packages")];
+        lock.pin_packages(&mut packages, LockPolicy::LowerBound, &mut Vec::new());
+        assert_eq!(packages[0].version.to_string(), ">=2.31.0");
+    }
+
+    #[test]
+    fn test_compatible_prerelease() {
+        let compatible = |locked: &str| {
+            let version: Version = locked.parse().unwrap();
+            specifiers_from_lock(&version, LockPolicy::Compatible).to_string()
+        };
+
+        assert_eq!(compatible("2.0rc1"), "~=2.0rc1");
+        assert_eq!(compatible("1.4.2.dev3"), "~=1.4.dev3");
+        assert_eq!(compatible("3.1.post2"), "~=3.1");
+    }
+
+    #[test]
+    fn test_pipfile_hash() {
+        let pipfile = r#"[packages]
+requests = "*"
+"zope.interface" = {version = ">=5", markers = "os_name == 'posix'"}
+
+[dev-packages]
+pytest = "*"
+
+[docs]
+sphinx = "*"
+
+[requires]
+python_version = "3.11"
+"#;
+        // calculated with Pipenv's algorithm in Python
+        let expected = "60bab5046c441e1a221380e2b07b4412691f24a1e2f78fb9ae6c5bd97733c78a";
+
+        assert_eq!(pipfile_hash(pipfile).unwrap(), expected);
+
+        let lock = PipfileLock::parse(&format!(
+            r#"{{"_meta": {{"hash": {{"sha256": "{}"}}}}, "default": {{}}}}"#,
+            expected
+        ))
+        .unwrap();
+        assert!(lock.is_up_to_date(pipfile).unwrap());
+        assert!(!lock
+            .is_up_to_date(&pipfile.replace("pytest", "pytest-cov"))
+            .unwrap());
+    }
+
+    #[test]
+    fn test_transitive_constraints() {
+        let lock = PipfileLock::parse(
+            r#"{
+    "default": {
+        "certifi": {"version": "==2024.2.2"},
+        "requests": {"version": "==2.31.0"},
+        "pywin32": {"markers": "sys_platform == 'win32'", "version": "==306"}
+    },
+    "develop": {
+        "certifi": {"version": "==2023.1.1"},
+        "pytest": {"version": "==8.0.0"},
+        "iniconfig": {"version": "==2.0.0"}
+    }
+}"#,
+        )
+        .unwrap();
+
+        let packages = vec![
+            package("Requests", "*", "packages"),
+            package("pytest", "*", "dev-packages"),
+        ];
+
+        assert_eq!(
+            lock.transitive_constraints(&packages),
+            vec![
+                "certifi==2024.2.2",
+                "pywin32==306; sys_platform == 'win32'",
+                "iniconfig==2.0.0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exact_constraints() {
+        let lock = PipfileLock::parse(
+            r#"{
+    "_meta": {"requires": {"python_version": "3.11"}},
+    "default": {
+        "requests": {"index": "pypi", "version": "==2.31.0"},
+        "pywin32": {"markers": "sys_platform == 'win32'", "version": "==306"},
+        "mylib": {"git": "https://github.com/org/mylib.git", "ref": "0a1b2c3", "version": "==1.0"}
+    },
+    "develop": {
+        "requests": {"version": "==2.30.0"},
+        "pytest": {"version": "==8.0.0"}
+    }
+}"#,
+        )
+        .unwrap();
+        assert_eq!(lock.python_version.as_deref(), Some("3.11"));
+
+        let mut diagnostics = Vec::new();
+        // declared packages are constrained too, their specifiers may allow other versions
+        assert_eq!(
+            lock.exact_constraints(&mut diagnostics),
+            vec![
+                "pywin32==306; sys_platform == 'win32'",
+                "requests==2.31.0",
+                "pytest==8.0.0",
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].message,
+            "Package requests is locked in several versions, 2.31.0 is kept"
+        );
+
+        let mut packages = vec![Package {
+            source: Some(PackageSource::Git {
+                url: "https://github.com/org/mylib.git".to_string(),
+                reference: Some(GitReference::Branch("main".to_string())),
+                subdirectory: None,
+            }),
+            ..package("mylib", "*", "packages")
+        }];
+        assert_eq!(lock.pin_git_sources(&mut packages), 1);
+        assert!(matches!(
+            &packages[0].source,
+            Some(PackageSource::Git {
+                reference: Some(GitReference::Rev(commit)),
+                ..
+            }) if commit == "0a1b2c3"
+        ));
+    }
+
+    #[test]
+    fn test_verify_uv_lock() {
+        let lock = PipfileLock::parse(
+            r#"{
+    "default": {
+        "requests": {"hashes": ["sha256:aaa", "sha256:bbb"], "version": "==2.31.0"},
+        "idna": {"hashes": ["sha256:ccc"], "version": "==3.6"},
+        "certifi": {"hashes": ["sha256:ddd"], "version": "==2024.2.2"}
+    }
+}"#,
+        )
+        .unwrap();
+        let uv_lock = r#"version = 1
+
+[[package]]
+name = "my-service"
+version = "0.1.0"
+source = { virtual = "." }
+
+[[package]]
+name = "requests"
+version = "2.31.0"
+source = { registry = "https://pypi.org/simple" }
+sdist = { url = "https://files/requests-2.31.0.tar.gz", hash = "sha256:aaa" }
+wheels = [
+    { url = "https://files/requests-2.31.0-py3-none-any.whl", hash = "sha256:bbb" },
+]
+
+[[package]]
+name = "idna"
+version = "3.7"
+source = { registry = "https://pypi.org/simple" }
+
+[[package]]
+name = "urllib3"
+version = "2.2.0"
+source = { registry = "https://pypi.org/simple" }
+"#;
+        let document = toml_edit::Document::parse(uv_lock.to_string()).unwrap();
+
+        let diagnostics = lock.verify_uv_lock(&document);
+
+        let messages: Vec<&str> = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.message.as_str())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Package idna is locked at 3.6 in Pipfile.lock",
+                "Package urllib3 is not in Pipfile.lock",
+                "Package certifi of Pipfile.lock is not in uv.lock",
+            ]
+        );
+        assert!(diagnostics[0].is_error() && diagnostics[1].is_error());
+        assert!(!diagnostics[2].is_error());
+        assert_eq!(diagnostics[0].location(uv_lock).unwrap().0, 19);
+
+        let tampered = uv_lock.replace("sha256:bbb", "sha256:eee");
+        let document = toml_edit::Document::parse(tampered).unwrap();
+        assert_eq!(
+            lock.verify_uv_lock(&document)[0].message,
+            "A file of package requests has a hash not in Pipfile.lock"
+        );
+    }
+}
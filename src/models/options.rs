@@ -0,0 +1,232 @@
+use crate::models::package::canonicalize_name;
+use crate::models::project::ProjectMetadata;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DirectReferenceStyle {
+    /// `url =` / `path =` entries in `[tool.uv.sources]`
+    #[default]
+    Sources,
+    /// PEP 508 `name @ url` requirements in the dependency arrays
+    Pep508,
+}
+
+impl DirectReferenceStyle {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "sources" => Some(DirectReferenceStyle::Sources),
+            "pep508" => Some(DirectReferenceStyle::Pep508),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PrereleasePolicy {
+    /// Pre-releases are allowed for every package, as Pipenv does
+    #[default]
+    Allow,
+    /// Pre-releases only when explicitly requested or nothing else satisfies the requirement
+    IfNecessaryOrExplicit,
+}
+
+impl PrereleasePolicy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "allow" => Some(PrereleasePolicy::Allow),
+            "if-necessary-or-explicit" => Some(PrereleasePolicy::IfNecessaryOrExplicit),
+            _ => None,
+        }
+    }
+
+    pub fn as_uv(&self) -> &'static str {
+        match self {
+            PrereleasePolicy::Allow => "allow",
+            PrereleasePolicy::IfNecessaryOrExplicit => "if-necessary-or-explicit",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LockPolicy {
+    /// `==` the locked version
+    Exact,
+    /// `>=` the locked version
+    LowerBound,
+    /// `~=` the locked version, allowing upgrades within its major version
+    Compatible,
+}
+
+impl LockPolicy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "exact" => Some(LockPolicy::Exact),
+            "lower-bound" => Some(LockPolicy::LowerBound),
+            "compatible" => Some(LockPolicy::Compatible),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum BuildBackend {
+    /// What pip uses to install a Pipenv project without a build backend of its own
+    #[default]
+    Setuptools,
+    Hatchling,
+    UvBuild,
+    Flit,
+}
+
+impl BuildBackend {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().replace('-', "_").as_str() {
+            "setuptools" => Some(BuildBackend::Setuptools),
+            "hatchling" => Some(BuildBackend::Hatchling),
+            "uv_build" => Some(BuildBackend::UvBuild),
+            "flit" => Some(BuildBackend::Flit),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BuildBackend::Setuptools => "setuptools",
+            BuildBackend::Hatchling => "hatchling",
+            BuildBackend::UvBuild => "uv_build",
+            BuildBackend::Flit => "flit",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum ScriptStyle {
+    /// `[tool.poe.tasks]` for poethepoet
+    #[default]
+    Poe,
+    /// Rules of a Makefile written next to pyproject.toml
+    Makefile,
+    /// Recipes of a justfile written next to pyproject.toml
+    Justfile,
+    /// `[project.scripts]` entry points, for scripts calling a function
+    ProjectScripts,
+}
+
+impl ScriptStyle {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "poe" => Some(ScriptStyle::Poe),
+            "makefile" => Some(ScriptStyle::Makefile),
+            "justfile" => Some(ScriptStyle::Justfile),
+            "project-scripts" => Some(ScriptStyle::ProjectScripts),
+            _ => None,
+        }
+    }
+
+    /// Name of the file the scripts are written to, when they are not part of pyproject.toml
+    pub fn file_name(&self) -> Option<&'static str> {
+        match self {
+            ScriptStyle::Makefile => Some("Makefile"),
+            ScriptStyle::Justfile => Some("justfile"),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DuplicatePolicy {
+    /// Keep every entry and print a warning
+    #[default]
+    Report,
+    /// Combine entries of one category, drop group entries already in [packages]
+    Merge,
+    /// Stop the conversion
+    Reject,
+}
+
+impl DuplicatePolicy {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "report" => Some(DuplicatePolicy::Report),
+            "merge" => Some(DuplicatePolicy::Merge),
+            "reject" => Some(DuplicatePolicy::Reject),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ExportOptions {
+    pub direct_references: DirectReferenceStyle,
+    // used when the Pipfile sets allow_prereleases = true
+    pub prerelease: PrereleasePolicy,
+    // Pipenv category name to dependency group name
+    pub group_names: HashMap<String, String>,
+    // bounds for `*` dependencies taken from Pipfile.lock, the lock is ignored when unset
+    pub lock_policy: Option<LockPolicy>,
+    // pin every package to its Pipfile.lock version so that `uv lock` reproduces it,
+    // the resulting uv.lock is checked with `verify_uv_lock`
+    pub uv_lock: bool,
+    // pin transitive packages from Pipfile.lock through constraint-dependencies
+    pub lock_constraints: bool,
+    // refuse to use a Pipfile.lock whose hash does not match the Pipfile
+    pub strict_lock: bool,
+    // what to do with packages listed several times under equivalent names
+    pub duplicates: DuplicatePolicy,
+    // write PEP 503 names instead of the spelling used in the Pipfile
+    pub normalize_names: bool,
+    // write the output even when some Pipfile entries could not be parsed
+    pub best_effort: bool,
+    // form in which Pipfile [scripts] are written
+    pub scripts: ScriptStyle,
+    // backend written to [build-system] when the project is a package
+    pub build_backend: BuildBackend,
+    // name, version and description written to [project]
+    pub project: ProjectMetadata,
+}
+
+impl ExportOptions {
+    /// Name of the `[dependency-groups]` entry a Pipenv category is exported to
+    pub fn group_name(&self, category: &str) -> String {
+        if let Some(group_name) = self.group_names.get(category) {
+            return normalize_group_name(group_name);
+        }
+        match category {
+            "dev-packages" => "dev".to_string(),
+            _ => normalize_group_name(category.trim_end_matches("-packages")),
+        }
+    }
+}
+
+/// Group names are normalized the same way as package names, see PEP 735
+fn normalize_group_name(name: &str) -> String {
+    canonicalize_name(name)
+}
+
+/// Parses a mapping such as `docs=documentation,test-packages=test`
+pub fn parse_group_names(value: &str) -> HashMap<String, String> {
+    value
+        .split(',')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(category, group)| (category.trim().to_string(), group.trim().to_string()))
+        .filter(|(category, group)| !category.is_empty() && !group.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_name() {
+        let options = ExportOptions {
+            group_names: parse_group_names("docs=Documentation, broken"),
+            ..Default::default()
+        };
+
+        assert_eq!(options.group_name("dev-packages"), "dev");
+        assert_eq!(options.group_name("test-packages"), "test");
+        assert_eq!(options.group_name("lint_Tools"), "lint-tools");
+        assert_eq!(options.group_name("docs"), "documentation");
+    }
+}
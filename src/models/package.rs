@@ -1,46 +1,192 @@
+use crate::models::source::normalize_index_name;
+use crate::models::toml_string;
+use crate::models::version::VersionSpecifiers;
+
 pub trait UVPackage {
     fn as_uv(&self) -> String;
-    fn state_source(&self) -> String;
+    fn as_uv_direct_reference(&self) -> Option<String>;
+    fn state_source(&self) -> Option<String>;
+}
+
+/// Name used to compare packages, see PEP 503
+pub fn canonicalize_name(name: &str) -> String {
+    let mut normalized: String = String::new();
+    for character in name.trim().to_lowercase().chars() {
+        if matches!(character, '-' | '_' | '.') {
+            if !normalized.ends_with('-') {
+                normalized.push('-');
+            }
+        } else {
+            normalized.push(character);
+        }
+    }
+    normalized
+}
+
+/// Pipfile table holding the main project dependencies
+pub const DEFAULT_CATEGORY: &str = "packages";
+
+pub enum GitReference {
+    Branch(String),
+    Tag(String),
+    Rev(String),
+}
+
+pub enum PackageSource {
+    Git {
+        url: String,
+        reference: Option<GitReference>,
+        subdirectory: Option<String>,
+    },
+    Path {
+        path: String,
+        editable: bool,
+    },
+    Url {
+        url: String,
+    },
 }
 
 pub struct Package {
     pub name: String,
-    pub version: String,
+    pub version: VersionSpecifiers,
     pub index: Option<String>,
     pub extras: Option<Vec<String>>,
-    pub is_dev: bool,
+    pub source: Option<PackageSource>,
+    pub markers: Option<String>,
+    pub category: String,
 }
 
-impl UVPackage for Package {
-    fn as_uv(&self) -> String {
-        let mut result_string: String = r#"""#.to_string();
+impl Package {
+    pub fn is_default(&self) -> bool {
+        self.category == DEFAULT_CATEGORY
+    }
+
+    /// Pipenv lists the project itself as a path dependency pointing to `.`
+    pub fn is_project_itself(&self) -> bool {
+        match &self.source {
+            Some(PackageSource::Path { path, .. }) => {
+                matches!(path.trim().trim_end_matches(['/', '\\']), "" | ".")
+            }
+            _ => false,
+        }
+    }
 
-        result_string.push_str(&self.name);
+    /// URL usable in a PEP 508 `name @ url` requirement, editable sources have none
+    pub fn direct_reference_url(&self) -> Option<String> {
+        match &self.source {
+            Some(PackageSource::Url { url }) => Some(url.clone()),
+            Some(PackageSource::Path {
+                path,
+                editable: false,
+            }) => {
+                if path.starts_with('/') {
+                    Some(format!("file://{}", path))
+                } else {
+                    Some(format!(
+                        "file:///${{PROJECT_ROOT}}/{}",
+                        path.trim_start_matches("./")
+                    ))
+                }
+            }
+            _ => None,
+        }
+    }
 
-        if self.extras.is_some() {
-            let extras: String = self.extras.clone().unwrap().join(",");
+    fn requirement_name(&self) -> String {
+        let mut result_string: String = self.name.clone();
+
+        if let Some(extras) = &self.extras {
             result_string.push('[');
-            result_string.push_str(&extras);
+            result_string.push_str(&extras.join(","));
             result_string.push(']');
         }
 
-        if &self.version != "*" {
-            result_string.push_str(&self.version);
+        result_string
+    }
+}
+
+impl UVPackage for Package {
+    fn as_uv(&self) -> String {
+        let mut result_string: String = self.requirement_name();
+
+        if !self.version.is_any() {
+            result_string.push_str(&self.version.to_string());
+        }
+
+        if let Some(markers) = &self.markers {
+            result_string.push_str("; ");
+            result_string.push_str(markers);
         }
 
-        result_string.push('"');
+        toml_string(&result_string)
+    }
 
-        result_string
+    fn as_uv_direct_reference(&self) -> Option<String> {
+        let url: String = self.direct_reference_url()?;
+
+        let mut result_string: String = self.requirement_name();
+        result_string.push_str(" @ ");
+        result_string.push_str(&url);
+
+        if let Some(markers) = &self.markers {
+            // a space is required before the semicolon, otherwise it is part of the url
+            result_string.push_str(" ; ");
+            result_string.push_str(markers);
+        }
+
+        Some(toml_string(&result_string))
     }
 
-    fn state_source(&self) -> String {
-        let index_name: &str = self.index.as_ref().unwrap();
-        let string = format!(
-            "{package} = {{index=\"{index}\"}}",
-            index = index_name,
-            package = self.name,
-        );
-        string
+    fn state_source(&self) -> Option<String> {
+        let mut source_parts: Vec<String> = Vec::new();
+
+        match &self.source {
+            Some(PackageSource::Git {
+                url,
+                reference,
+                subdirectory,
+            }) => {
+                source_parts.push(format!("git={}", toml_string(url)));
+                match reference {
+                    Some(GitReference::Branch(branch)) => {
+                        source_parts.push(format!("branch={}", toml_string(branch)))
+                    }
+                    Some(GitReference::Tag(tag)) => {
+                        source_parts.push(format!("tag={}", toml_string(tag)))
+                    }
+                    Some(GitReference::Rev(rev)) => {
+                        source_parts.push(format!("rev={}", toml_string(rev)))
+                    }
+                    None => {}
+                }
+                if let Some(subdirectory) = subdirectory {
+                    source_parts.push(format!("subdirectory={}", toml_string(subdirectory)));
+                }
+            }
+            Some(PackageSource::Path { path, editable }) => {
+                source_parts.push(format!("path={}", toml_string(path)));
+                if *editable {
+                    source_parts.push("editable=true".to_string());
+                }
+            }
+            Some(PackageSource::Url { url }) => {
+                source_parts.push(format!("url={}", toml_string(url)));
+            }
+            None => {
+                let index_name: &str = self.index.as_ref()?;
+                source_parts.push(format!(
+                    "index={}",
+                    toml_string(&normalize_index_name(index_name))
+                ));
+            }
+        }
+
+        Some(format!(
+            "{package} = {{{source}}}",
+            package = toml_edit::Key::new(self.name.as_str()),
+            source = source_parts.join(", "),
+        ))
     }
 }
 
@@ -52,10 +198,12 @@ mod tests {
     fn test_as_uv_with_extras() {
         let package = Package {
             name: "requests".to_string(),
-            version: "==2.25.1".to_string(),
+            version: "==2.25.1".parse().unwrap(),
             index: None,
             extras: Some(vec!["socks".to_string()]),
-            is_dev: false,
+            source: None,
+            markers: None,
+            category: "packages".to_string(),
         };
 
         let expected = r#""requests[socks]==2.25.1""#;
@@ -63,17 +211,132 @@ mod tests {
         assert_eq!(result, expected);
     }
 
+    #[test]
+    fn test_as_uv_with_markers() {
+        let package = Package {
+            name: "pywin32".to_string(),
+            version: ">=1.0".parse().unwrap(),
+            index: None,
+            extras: None,
+            source: None,
+            markers: Some("sys_platform == 'win32'".to_string()),
+            category: "packages".to_string(),
+        };
+
+        let expected = r#""pywin32>=1.0; sys_platform == 'win32'""#;
+        assert_eq!(package.as_uv(), expected);
+    }
+
     #[test]
     fn test_state_source() {
         let package = Package {
             name: "requests".to_string(),
-            version: "2.25.1".to_string(),
+            version: "2.25.1".parse().unwrap(),
             index: Some("pypi".to_string()),
             extras: None,
-            is_dev: false,
+            source: None,
+            markers: None,
+            category: "packages".to_string(),
         };
 
         let expected = "requests = {index=\"pypi\"}".to_string();
-        assert_eq!(package.state_source(), expected);
+        assert_eq!(package.state_source().unwrap(), expected);
+
+        let dotted_package = Package {
+            name: "zope.interface".to_string(),
+            ..package
+        };
+        assert_eq!(
+            dotted_package.state_source().unwrap(),
+            "\"zope.interface\" = {index=\"pypi\"}"
+        );
+    }
+
+    #[test]
+    fn test_state_source_git() {
+        let package = Package {
+            name: "mylib".to_string(),
+            version: "*".parse().unwrap(),
+            index: None,
+            extras: None,
+            source: Some(PackageSource::Git {
+                url: "https://github.com/org/mylib.git".to_string(),
+                reference: Some(GitReference::Tag("v1.2".to_string())),
+                subdirectory: Some("pkg".to_string()),
+            }),
+            markers: None,
+            category: "packages".to_string(),
+        };
+
+        let expected =
+            r#"mylib = {git="https://github.com/org/mylib.git", tag="v1.2", subdirectory="pkg"}"#;
+        assert_eq!(package.state_source().unwrap(), expected);
+        assert_eq!(package.as_uv(), r#""mylib""#);
+    }
+
+    #[test]
+    fn test_state_source_path() {
+        let package = Package {
+            name: "mypkg".to_string(),
+            version: "*".parse().unwrap(),
+            index: None,
+            extras: None,
+            source: Some(PackageSource::Path {
+                path: "./libs/mypkg".to_string(),
+                editable: true,
+            }),
+            markers: None,
+            category: "packages".to_string(),
+        };
+
+        let expected = r#"mypkg = {path="./libs/mypkg", editable=true}"#;
+        assert_eq!(package.state_source().unwrap(), expected);
+        assert!(!package.is_project_itself());
+
+        let project_package = Package {
+            source: Some(PackageSource::Path {
+                path: "./".to_string(),
+                editable: true,
+            }),
+            ..package
+        };
+        assert!(project_package.is_project_itself());
+    }
+
+    #[test]
+    fn test_direct_reference() {
+        let package = Package {
+            name: "pkg".to_string(),
+            version: "*".parse().unwrap(),
+            index: None,
+            extras: None,
+            source: Some(PackageSource::Url {
+                url: "https://host/pkg-1.0.tar.gz".to_string(),
+            }),
+            markers: Some("os_name == 'posix'".to_string()),
+            category: "packages".to_string(),
+        };
+
+        assert_eq!(
+            package.state_source().unwrap(),
+            r#"pkg = {url="https://host/pkg-1.0.tar.gz"}"#
+        );
+        assert_eq!(
+            package.as_uv_direct_reference().unwrap(),
+            r#""pkg @ https://host/pkg-1.0.tar.gz ; os_name == 'posix'""#
+        );
+
+        let wheel_package = Package {
+            source: Some(PackageSource::Path {
+                path: "./wheels/pkg-1.0-py3-none-any.whl".to_string(),
+                editable: false,
+            }),
+            markers: None,
+            ..package
+        };
+        assert_eq!(
+            wheel_package.as_uv_direct_reference().unwrap(),
+            r#""pkg @ file:///${PROJECT_ROOT}/wheels/pkg-1.0-py3-none-any.whl""#
+        );
     }
 }
@@ -1,32 +1,36 @@
 pub struct Pipenv {
     pub python_version: String,
-    pub allow_prereleases: Option<String>,
+    pub allow_prereleases: Option<bool>,
+}
+
+impl Pipenv {
+    /// `requires-python` specifier, none when the Pipfile has no `[requires]` version,
+    /// Pipenv's bare `3.11` means this version or newer
+    pub fn requires_python(&self) -> Option<String> {
+        let python_version: &str = self.python_version.trim();
+        if python_version.is_empty() {
+            return None;
+        }
+        if python_version.starts_with(['=', '!', '<', '>', '~']) {
+            return Some(python_version.to_string());
+        }
+        Some(format!(">={}", python_version))
+    }
 }
 
 pub trait PipenvRequirements {
-    fn set_py_version(&mut self, value: &str) -> Result<(), std::io::Error>;
-    fn set_prereleases_status(&mut self, value: &str) -> Result<(), std::io::Error>;
+    fn set_py_version(&mut self, value: &str);
+    fn set_prereleases_status(&mut self, value: bool);
 
     fn new() -> Self;
 }
 
 impl PipenvRequirements for Pipenv {
-    fn set_py_version(&mut self, value: &str) -> Result<(), std::io::Error> {
-        let split: Vec<&str> = value.split('=').collect();
-        let value = split[1]
-            .trim()
-            .trim_start_matches('"')
-            .trim_end_matches('"');
-        self.python_version = value.to_string();
-        Ok(())
+    fn set_py_version(&mut self, value: &str) {
+        self.python_version = value.trim().to_string();
     }
-    fn set_prereleases_status(&mut self, value: &str) -> Result<(), std::io::Error> {
-        let split: Vec<&str> = value.split('=').collect();
-
-        let value = split[1].trim().to_string();
+    fn set_prereleases_status(&mut self, value: bool) {
         self.allow_prereleases = Some(value);
-
-        Ok(())
     }
 
     fn new() -> Self {
@@ -42,26 +46,31 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_set_py_version() -> Result<(), std::io::Error> {
+    fn test_set_py_version() {
         let mut pipenv = Pipenv {
             python_version: "".to_string(),
             allow_prereleases: None,
         };
 
-        pipenv.set_py_version("python_version = \"3.8\"")?;
+        pipenv.set_py_version("3.8");
         assert_eq!(pipenv.python_version, "3.8");
-        Ok(())
+        assert_eq!(pipenv.requires_python().unwrap(), ">=3.8");
+
+        pipenv.set_py_version(">=3.8,<4");
+        assert_eq!(pipenv.requires_python().unwrap(), ">=3.8,<4");
+
+        pipenv.set_py_version("");
+        assert!(pipenv.requires_python().is_none());
     }
 
     #[test]
-    fn test_set_prereleases_status() -> Result<(), std::io::Error> {
+    fn test_set_prereleases_status() {
         let mut pipenv = Pipenv {
             python_version: "".to_string(),
             allow_prereleases: None,
         };
 
-        pipenv.set_prereleases_status("allow_prereleases = true")?;
-        assert_eq!(pipenv.allow_prereleases.unwrap(), "true");
-        Ok(())
+        pipenv.set_prereleases_status(true);
+        assert!(pipenv.allow_prereleases.unwrap());
     }
 }
@@ -1,126 +1,486 @@
-use crate::models::package::{Package, UVPackage};
+use crate::errors::Diagnostic;
+use crate::models::build::{build_system, supports_layout, supports_version};
+use crate::models::options::{
+    BuildBackend, DirectReferenceStyle, ExportOptions, PrereleasePolicy, ScriptStyle,
+};
+use crate::models::package::{canonicalize_name, Package, UVPackage};
 use crate::models::pipenv::Pipenv;
+use crate::models::project::{readme_needs_content_type, ProjectMetadata, ProjectVersion};
+use crate::models::script::Script;
 use crate::models::source::{Source, UVSource};
+use crate::models::toml_string;
 
 pub trait PipenvUVInterface {
-    fn export(&self) -> String;
-    fn _export_project_part(&self) -> (String, Vec<&Package>, Vec<&Package>);
-    fn _prepare_dev_dependencies(&self, dev_dependencies: Vec<&Package>) -> String;
-    fn _prepare_sources(&self, indexed_packages: Vec<&Package>) -> String;
+    fn export(&self, diagnostics: &mut Vec<Diagnostic>) -> String;
+    fn _export_project_part(
+        &self,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> (String, Vec<&Package>, Vec<&Package>);
+    fn _prepare_dependency_groups(&self, grouped_dependencies: Vec<&Package>) -> String;
+    fn _prepare_sources(
+        &self,
+        sourced_packages: Vec<&Package>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> String;
+    fn _prepare_uv_settings(&self) -> String;
+    fn _prepare_build_system(&self, diagnostics: &mut Vec<Diagnostic>) -> String;
+    fn _prepare_poe_tasks(&self) -> String;
+    fn _dependency_line(&self, package: &Package) -> String;
+    fn _uses_uv_source(&self, package: &Package) -> bool;
+    fn _report_prereleases(&self, diagnostics: &mut Vec<Diagnostic>);
+    fn is_package(&self) -> bool;
+    fn credential_variables(&self) -> Vec<(String, String)>;
+    fn scripts_file(&self, diagnostics: &mut Vec<Diagnostic>) -> Option<String>;
 }
 
 pub struct PipenvContent {
     pub sources: Vec<Source>,
     pub packages: Vec<Package>,
     pub pipenv: Pipenv,
+    // pins of transitive packages taken from Pipfile.lock
+    pub constraints: Vec<String>,
+    pub scripts: Vec<Script>,
+    pub options: ExportOptions,
 }
 
 impl PipenvUVInterface for PipenvContent {
-    fn _export_project_part(&self) -> (String, Vec<&Package>, Vec<&Package>) {
+    fn _export_project_part(
+        &self,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> (String, Vec<&Package>, Vec<&Package>) {
         let mut resulting_lines: String = String::new();
 
-        let static_lines: String = r#"[project]
-name = "type-your-project-name-here"
-version = "0.1.0"
-description = "Add your description here"
-readme = "README.md""#
-            .to_string();
-
         // start with general project data
-        resulting_lines.push_str(&static_lines);
-        resulting_lines.push('\n');
+        let project: &ProjectMetadata = &self.options.project;
+        resulting_lines.push_str("[project]\n");
+        resulting_lines.push_str(&format!("name = {}\n", toml_string(&project.name)));
+        match &project.version {
+            ProjectVersion::Static(version) => {
+                resulting_lines.push_str(&format!("version = {}\n", toml_string(version)));
+            }
+            _ => resulting_lines.push_str("dynamic = [\"version\"]\n"),
+        }
+        resulting_lines.push_str(&format!(
+            "description = {}\n",
+            toml_string(&project.description)
+        ));
+        match &project.readme {
+            Some(readme) if readme_needs_content_type(readme) => {
+                resulting_lines.push_str(&format!(
+                    "readme = {{file = {}, content-type = \"text/plain\"}}\n",
+                    toml_string(readme)
+                ));
+            }
+            Some(readme) => {
+                resulting_lines.push_str(&format!("readme = {}\n", toml_string(readme)));
+            }
+            None => {}
+        }
+        if let Some((license, license_file)) = &project.license {
+            match license {
+                Some(license) => {
+                    resulting_lines.push_str(&format!("license = {}\n", toml_string(license)))
+                }
+                None => diagnostics.push(Diagnostic::warning(format!(
+                    "{} does not tell whether later versions of the GNU license apply, set `license` to its -only or -or-later identifier",
+                    license_file
+                ))),
+            }
+            resulting_lines.push_str(&format!(
+                "license-files = [{}]\n",
+                toml_string(license_file)
+            ));
+        }
+        if !project.authors.is_empty() {
+            resulting_lines.push_str("authors = [\n");
+            for author in &project.authors {
+                let mut fields: Vec<String> = vec![format!("name = {}", toml_string(&author.name))];
+                if let Some(email) = &author.email {
+                    fields.push(format!("email = {}", toml_string(email)));
+                }
+                resulting_lines.push_str(&format!("\t{{{}}},\n", fields.join(", ")));
+            }
+            resulting_lines.push_str("]\n");
+        }
 
-        let python_v_line: String = format!("requires-python = \"{}\"", self.pipenv.python_version);
-        resulting_lines.push_str(&python_v_line);
-        resulting_lines.push('\n');
+        if let Some(requires_python) = self.pipenv.requires_python() {
+            resulting_lines.push_str(&format!(
+                "requires-python = {}\n",
+                toml_string(&requires_python)
+            ));
+        }
 
-        let mut dev_dependencies: Vec<&Package> = Vec::new();
-        let mut indexed_packages: Vec<&Package> = Vec::new();
+        let mut grouped_dependencies: Vec<&Package> = Vec::new();
+        let mut sourced_packages: Vec<&Package> = Vec::new();
 
         resulting_lines.push_str("dependencies = [\n");
         for package in &self.packages {
-            if package.is_dev {
-                //     dev packages are declared later in a separate group
-                dev_dependencies.push(package);
+            if package.is_project_itself() {
+                //     the project is not a dependency of itself
+                continue;
+            }
+
+            if self._uses_uv_source(package) {
+                //     package sources are declared later in a separate group
+                sourced_packages.push(package);
+            }
+
+            if !package.is_default() {
+                //     dev and other categories are declared later in dependency groups
+                grouped_dependencies.push(package);
                 continue;
             }
             resulting_lines.push('\t');
-            resulting_lines.push_str(&package.as_uv());
+            resulting_lines.push_str(&self._dependency_line(package));
             resulting_lines.push_str(",\n");
+        }
+        resulting_lines.push_str("]\n");
 
-            if package.index.is_some() {
-                //     packages are declared later in a separate group
-                indexed_packages.push(package);
+        if !project.urls.is_empty() {
+            resulting_lines.push_str("\n[project.urls]\n");
+            for (label, url) in &project.urls {
+                resulting_lines.push_str(&format!(
+                    "{} = {}\n",
+                    toml_edit::Key::new(label.as_str()),
+                    toml_string(url)
+                ));
+            }
+        }
+
+        if self.options.scripts == ScriptStyle::ProjectScripts && !self.scripts.is_empty() {
+            let mut entry_points: Vec<String> = Vec::new();
+            for script in &self.scripts {
+                match script.as_entry_point() {
+                    Ok(entry_point) => entry_points.push(entry_point),
+                    Err(reason) => diagnostics.push(Diagnostic::warning(format!(
+                        "Script {} is left out: {}",
+                        script.name, reason
+                    ))),
+                }
+            }
+            if !entry_points.is_empty() {
+                resulting_lines.push_str("\n[project.scripts]\n");
+                for entry_point in entry_points {
+                    resulting_lines.push_str(&entry_point);
+                    resulting_lines.push('\n');
+                }
             }
         }
-        resulting_lines.push_str("]\n");
 
-        (resulting_lines, dev_dependencies, indexed_packages)
+        (resulting_lines, grouped_dependencies, sourced_packages)
     }
 
-    fn _prepare_dev_dependencies(&self, dev_dependencies: Vec<&Package>) -> String {
+    fn _prepare_dependency_groups(&self, grouped_dependencies: Vec<&Package>) -> String {
+        // groups keep the order in which their categories appear in the Pipfile
+        let mut groups: Vec<(String, Vec<&Package>)> = Vec::new();
+        for package in grouped_dependencies {
+            let group_name: String = self.options.group_name(&package.category);
+            match groups.iter_mut().find(|(name, _)| *name == group_name) {
+                Some((_, group_packages)) => group_packages.push(package),
+                None => groups.push((group_name, vec![package])),
+            }
+        }
+
         let mut resulting_lines: String = String::new();
         resulting_lines.push_str("[dependency-groups]\n");
-        resulting_lines.push_str("dev = [\n");
-        for package in dev_dependencies {
-            resulting_lines.push('\t');
-            resulting_lines.push_str(&package.as_uv());
-            resulting_lines.push_str(",\n");
+        for (group_name, group_packages) in groups {
+            resulting_lines.push_str(&format!("{} = [\n", toml_edit::Key::new(group_name)));
+            for package in group_packages {
+                resulting_lines.push('\t');
+                resulting_lines.push_str(&self._dependency_line(package));
+                resulting_lines.push_str(",\n");
+            }
+            resulting_lines.push_str("]\n");
         }
-        resulting_lines.push_str("]\n");
         resulting_lines
     }
 
-    fn _prepare_sources(&self, indexed_packages: Vec<&Package>) -> String {
+    fn _prepare_sources(
+        &self,
+        sourced_packages: Vec<&Package>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> String {
         let mut resulting_lines: String = String::new();
 
         for source in &self.sources {
             resulting_lines.push_str("[[tool.uv.index]]\n");
             resulting_lines.push_str(&source.as_uv());
+            source.report_settings(diagnostics);
             resulting_lines.push_str("\n\n");
         }
 
-        if !indexed_packages.is_empty() {
+        if !sourced_packages.is_empty() {
             resulting_lines.push_str("[tool.uv.sources]\n");
-            for dependant_package in indexed_packages {
-                let ln: String = dependant_package.state_source();
-                resulting_lines.push_str(&ln);
-                resulting_lines.push('\n');
+            // a package listed in several categories takes one source, keyed by canonical name
+            let mut written_sources: Vec<(String, String)> = Vec::new();
+            for dependant_package in sourced_packages {
+                let Some(ln) = dependant_package.state_source() else {
+                    continue;
+                };
+                let canonical_name: String = canonicalize_name(&dependant_package.name);
+                let source: String = ln
+                    .split_once(" = ")
+                    .map(|(_, source)| source.to_string())
+                    .unwrap_or_default();
+                match written_sources
+                    .iter()
+                    .find(|(name, _)| *name == canonical_name)
+                {
+                    Some((_, written_source)) if *written_source == source => {}
+                    Some((_, written_source)) => diagnostics.push(Diagnostic::warning(format!(
+                        "Package {} in [{}] has another source than before, keeping {}",
+                        dependant_package.name, dependant_package.category, written_source
+                    ))),
+                    None => {
+                        resulting_lines.push_str(&ln);
+                        resulting_lines.push('\n');
+                        written_sources.push((canonical_name, source));
+                    }
+                }
             }
         }
 
         resulting_lines
     }
 
-    fn export(&self) -> String {
+    fn _prepare_uv_settings(&self) -> String {
+        let mut settings_lines: Vec<String> = Vec::new();
+
+        if self.is_package() {
+            settings_lines.push("package = true".to_string());
+        }
+
+        let mut insecure_hosts: Vec<String> = Vec::new();
+        for source in self.sources.iter().filter(|source| source.is_insecure()) {
+            let host: String = source.host();
+            if !insecure_hosts.contains(&host) {
+                insecure_hosts.push(host);
+            }
+        }
+        if !insecure_hosts.is_empty() {
+            let hosts: Vec<String> = insecure_hosts
+                .iter()
+                .map(|host| toml_string(host))
+                .collect();
+            settings_lines.push(format!("allow-insecure-host = [{}]", hosts.join(", ")));
+        }
+
+        if !self.constraints.is_empty() {
+            let mut constraints_line: String = "constraint-dependencies = [\n".to_string();
+            for constraint in &self.constraints {
+                constraints_line.push('\t');
+                constraints_line.push_str(&toml_string(constraint));
+                constraints_line.push_str(",\n");
+            }
+            constraints_line.push(']');
+            settings_lines.push(constraints_line);
+        }
+
+        if self.pipenv.allow_prereleases.unwrap_or(false) {
+            settings_lines.push(format!(
+                "prerelease = \"{}\"",
+                self.options.prerelease.as_uv()
+            ));
+        }
+
+        if settings_lines.is_empty() {
+            return String::new();
+        }
+
         let mut resulting_lines: String = String::new();
+        resulting_lines.push_str("[tool.uv]\n");
+        for line in settings_lines {
+            resulting_lines.push_str(&line);
+            resulting_lines.push('\n');
+        }
+        resulting_lines
+    }
 
-        let (project_lines, dev_dependencies, indexed_packages) = self._export_project_part();
+    fn _prepare_build_system(&self, diagnostics: &mut Vec<Diagnostic>) -> String {
+        let project: &ProjectMetadata = &self.options.project;
+        let mut backend: BuildBackend = self.options.build_backend;
+        if !supports_version(backend, project) {
+            diagnostics.push(Diagnostic::warning(format!(
+                "{} cannot compute the project version, using setuptools instead",
+                backend.name()
+            )));
+            backend = BuildBackend::Setuptools;
+        }
+        if !supports_layout(backend, project) {
+            diagnostics.push(Diagnostic::warning(format!(
+                "{} cannot find the packages listed in setup.py, using setuptools instead",
+                backend.name()
+            )));
+            backend = BuildBackend::Setuptools;
+        }
+        build_system(backend, project)
+    }
+
+    fn _prepare_poe_tasks(&self) -> String {
+        let mut resulting_lines: String = String::new();
+        resulting_lines.push_str("[tool.poe.tasks]\n");
+        for script in &self.scripts {
+            resulting_lines.push_str(&script.as_poe_task());
+            resulting_lines.push('\n');
+        }
+        resulting_lines
+    }
+
+    fn _dependency_line(&self, package: &Package) -> String {
+        if self.options.direct_references == DirectReferenceStyle::Pep508 {
+            if let Some(direct_reference) = package.as_uv_direct_reference() {
+                return direct_reference;
+            }
+        }
+        package.as_uv()
+    }
+
+    fn _uses_uv_source(&self, package: &Package) -> bool {
+        if self.options.direct_references == DirectReferenceStyle::Pep508
+            && package.direct_reference_url().is_some()
+        {
+            // the location is already part of the requirement string
+            return false;
+        }
+        package.index.is_some() || package.source.is_some()
+    }
+
+    fn _report_prereleases(&self, diagnostics: &mut Vec<Diagnostic>) {
+        let prerelease_packages: Vec<&Package> = self
+            .packages
+            .iter()
+            .filter(|package| package.version.has_prerelease())
+            .collect();
+
+        for package in &prerelease_packages {
+            diagnostics.push(Diagnostic::info(format!(
+                "Package {} requests a pre-release: {}",
+                package.name, package.version
+            )));
+        }
+
+        let allow_prereleases: bool = self.pipenv.allow_prereleases.unwrap_or(false);
+        if allow_prereleases
+            && !prerelease_packages.is_empty()
+            && self.options.prerelease == PrereleasePolicy::Allow
+        {
+            diagnostics.push(Diagnostic::info(
+                "Pre-releases are only requested explicitly, consider --prerelease if-necessary-or-explicit".to_string(),
+            ));
+        } else if !allow_prereleases && !prerelease_packages.is_empty() {
+            diagnostics.push(Diagnostic::info(
+                "uv's default prerelease policy already allows explicitly requested pre-releases"
+                    .to_string(),
+            ));
+        }
+    }
+
+    fn credential_variables(&self) -> Vec<(String, String)> {
+        self.sources
+            .iter()
+            .flat_map(|source| source.credential_variables())
+            .collect()
+    }
+
+    fn scripts_file(&self, diagnostics: &mut Vec<Diagnostic>) -> Option<String> {
+        let rule = match self.options.scripts {
+            ScriptStyle::Makefile => Script::as_make_rule,
+            ScriptStyle::Justfile => Script::as_just_recipe,
+            _ => return None,
+        };
+        if self.scripts.is_empty() {
+            return None;
+        }
+
+        let mut names: Vec<&str> = Vec::new();
+        let mut rules: Vec<String> = Vec::new();
+        for script in &self.scripts {
+            match rule(script) {
+                Ok(rule) => {
+                    names.push(&script.name);
+                    rules.push(rule);
+                }
+                Err(reason) => diagnostics.push(Diagnostic::warning(format!(
+                    "Script {} is left out: {}",
+                    script.name, reason
+                ))),
+            }
+        }
+        if rules.is_empty() {
+            return None;
+        }
+
+        let mut resulting_lines: String = String::new();
+        resulting_lines.push_str("# converted from the Pipfile [scripts]\n");
+        if self.options.scripts == ScriptStyle::Makefile {
+            resulting_lines.push_str(&format!(".PHONY: {}\n", names.join(" ")));
+        }
+        for rule in rules {
+            resulting_lines.push('\n');
+            resulting_lines.push_str(&rule);
+        }
+        Some(resulting_lines)
+    }
+
+    fn is_package(&self) -> bool {
+        // a version computed at build time needs the project to be built
+        self.options.project.version.is_dynamic()
+            || self.options.project.layout.is_some()
+            || self
+                .packages
+                .iter()
+                .any(|package| package.is_project_itself())
+            // uv only installs [project.scripts] of projects it builds
+            || (self.options.scripts == ScriptStyle::ProjectScripts
+                && self
+                    .scripts
+                    .iter()
+                    .any(|script| script.as_entry_point().is_ok()))
+    }
+
+    fn export(&self, diagnostics: &mut Vec<Diagnostic>) -> String {
+        let mut resulting_lines: String = String::new();
+
+        let (project_lines, grouped_dependencies, sourced_packages) =
+            self._export_project_part(diagnostics);
 
         resulting_lines.push_str(&project_lines);
         resulting_lines.push('\n');
 
-        // adding some dev dependecies if there are any
-        if !dev_dependencies.is_empty() {
-            let dev_dependencies_lines = self._prepare_dev_dependencies(dev_dependencies);
-            resulting_lines.push_str(&dev_dependencies_lines);
+        // adding dev and other dependency groups if there are any
+        if !grouped_dependencies.is_empty() {
+            let dependency_groups_lines = self._prepare_dependency_groups(grouped_dependencies);
+            resulting_lines.push_str(&dependency_groups_lines);
+            resulting_lines.push('\n');
+        }
+
+        let uv_settings_lines = self._prepare_uv_settings();
+        if !uv_settings_lines.is_empty() {
+            resulting_lines.push_str(&uv_settings_lines);
             resulting_lines.push('\n');
         }
 
         // setting info on indexes and sources
-        if !self.sources.is_empty() {
-            let sources_lines = self._prepare_sources(indexed_packages);
+        if !self.sources.is_empty() || !sourced_packages.is_empty() {
+            let sources_lines = self._prepare_sources(sourced_packages, diagnostics);
             resulting_lines.push_str(&sources_lines);
             resulting_lines.push('\n');
         }
 
-        println!(
-            "Allow pre-releases handling not yet implemented. Current: {}",
-            self.pipenv
-                .allow_prereleases
-                .clone()
-                .unwrap_or("false".to_string())
-        );
+        if self.options.scripts == ScriptStyle::Poe && !self.scripts.is_empty() {
+            resulting_lines.push_str(&self._prepare_poe_tasks());
+            resulting_lines.push('\n');
+            diagnostics.push(Diagnostic::info(
+                "Pipfile scripts run with `uv run poe <name>` once poethepoet is added as a dev dependency".to_string(),
+            ));
+        }
+
+        if self.is_package() {
+            resulting_lines.push_str(&self._prepare_build_system(diagnostics));
+            resulting_lines.push('\n');
+        }
+
+        self._report_prereleases(diagnostics);
 
         resulting_lines
     }
@@ -129,107 +489,363 @@ readme = "README.md""#
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::package::{GitReference, PackageSource};
+    use crate::models::project::Author;
+    use crate::models::script::ScriptCommand;
+    use crate::models::testing::{empty_content, package};
+    use std::collections::HashMap;
 
     #[test]
     fn test_export_project_part() {
-        let packages = vec![
-            Package {
-                name: "requests".to_string(),
-                version: "2.25.1".to_string(),
-                index: None,
-                extras: None,
-                is_dev: false,
-            },
-            Package {
-                name: "uvicorn".to_string(),
-                version: "0.14.0".to_string(),
-                index: None,
-                extras: None,
-                is_dev: true,
+        let mut pipenv_content = PipenvContent {
+            packages: vec![
+                package("requests", "2.25.1", "packages"),
+                package("uvicorn", "0.14.0", "dev-packages"),
+            ],
+            ..empty_content()
+        };
+        pipenv_content.pipenv.python_version = "3.8".to_string();
+
+        let (project_lines, dev_packages, sourced_packages) =
+            pipenv_content._export_project_part(&mut Vec::new());
+
+        assert_eq!(dev_packages.len(), 1);
+        assert_eq!(sourced_packages.len(), 0);
+        assert!(project_lines.contains("requires-python = \">=3.8\"\n"));
+
+        pipenv_content.pipenv.python_version = "".to_string();
+        let (project_lines, _, _) = pipenv_content._export_project_part(&mut Vec::new());
+        assert!(!project_lines.contains("requires-python"));
+    }
+
+    #[test]
+    fn test_prepare_dependency_groups() {
+        let pipenv_content = PipenvContent {
+            packages: vec![
+                package("requests", "==2.25.1", "dev-packages"),
+                package("uvicorn", "==0.14.0", "dev-packages"),
+            ],
+            ..empty_content()
+        };
+
+        let packages_dup: Vec<&Package> = pipenv_content.packages.iter().collect();
+
+        let lines = pipenv_content._prepare_dependency_groups(packages_dup);
+
+        assert_eq!(
+            lines,
+            "[dependency-groups]\ndev = [\n\t\"requests==2.25.1\",\n\t\"uvicorn==0.14.0\",\n]\n"
+        );
+    }
+
+    #[test]
+    fn test_prepare_custom_dependency_groups() {
+        let pipenv_content = PipenvContent {
+            packages: vec![
+                package("sphinx", "*", "docs"),
+                package("pytest", "*", "test-packages"),
+                package("furo", "*", "docs"),
+            ],
+            options: ExportOptions {
+                group_names: HashMap::from([("docs".to_string(), "documentation".to_string())]),
+                ..Default::default()
             },
-        ];
+            ..empty_content()
+        };
+
+        let packages_dup: Vec<&Package> = pipenv_content.packages.iter().collect();
+
+        let lines = pipenv_content._prepare_dependency_groups(packages_dup);
 
-        let pipenv = Pipenv {
-            python_version: "3.8".to_string(),
-            allow_prereleases: Some("true".to_string()),
+        assert_eq!(
+            lines,
+            "[dependency-groups]\ndocumentation = [\n\t\"sphinx\",\n\t\"furo\",\n]\ntest = [\n\t\"pytest\",\n]\n"
+        );
+    }
+
+    #[test]
+    fn test_prepare_sources() {
+        let pipenv_content = PipenvContent {
+            sources: vec![Source {
+                name: "pypi".to_string(),
+                url: "https://pypi.org/simple".to_string(),
+                verify_ssl: Some(true),
+            }],
+            ..empty_content()
         };
+        let packages_dup: Vec<&Package> = pipenv_content.packages.iter().collect();
+
+        let lines = pipenv_content._prepare_sources(packages_dup, &mut Vec::new());
 
-        let sources = vec![Source {
-            name: "pypi".to_string(),
-            url: "https://pypi.org/simple".to_string(),
-            verify_ssl: Some("true".to_string()),
-        }];
+        assert_eq!(lines.lines().count(), 5);
+    }
 
+    #[test]
+    fn test_export_duplicate_sources() {
+        let indexed = |name: &str, index: &str, category: &str| Package {
+            index: Some(index.to_string()),
+            ..package(name, "*", category)
+        };
         let pipenv_content = PipenvContent {
-            packages,
-            pipenv,
-            sources,
+            packages: vec![
+                indexed("requests", "corp", "packages"),
+                indexed("Requests", "corp", "dev-packages"),
+                indexed("flask", "corp", "packages"),
+                indexed("flask", "mirror", "dev-packages"),
+            ],
+            ..empty_content()
         };
 
-        let (_, dev_packages, indexed_packages) = pipenv_content._export_project_part();
+        let exported = pipenv_content.export(&mut Vec::new());
 
-        assert_eq!(dev_packages.len(), 1);
-        assert_eq!(indexed_packages.len(), 0);
+        assert!(exported.contains(
+            "[tool.uv.sources]\nrequests = {index=\"corp\"}\nflask = {index=\"corp\"}\n"
+        ));
+        assert!(exported.parse::<toml_edit::DocumentMut>().is_ok());
+    }
+
+    #[test]
+    fn test_export_git_source() {
+        let pipenv_content = PipenvContent {
+            packages: vec![Package {
+                source: Some(PackageSource::Git {
+                    url: "https://github.com/org/mylib.git".to_string(),
+                    reference: Some(GitReference::Branch("main".to_string())),
+                    subdirectory: None,
+                }),
+                ..package("mylib", "*", "packages")
+            }],
+            ..empty_content()
+        };
+
+        let exported = pipenv_content.export(&mut Vec::new());
+
+        assert!(exported.contains("dependencies = [\n\t\"mylib\",\n]"));
+        assert!(exported.contains(
+            "[tool.uv.sources]\nmylib = {git=\"https://github.com/org/mylib.git\", branch=\"main\"}"
+        ));
+    }
+
+    #[test]
+    fn test_export_project_itself() {
+        let editable = |name: &str, path: &str, category: &str| Package {
+            source: Some(PackageSource::Path {
+                path: path.to_string(),
+                editable: true,
+            }),
+            ..package(name, "*", category)
+        };
+        let pipenv_content = PipenvContent {
+            packages: vec![
+                editable(".", ".", "packages"),
+                editable("mypkg", "./libs/mypkg", "dev-packages"),
+            ],
+            ..empty_content()
+        };
+
+        let exported = pipenv_content.export(&mut Vec::new());
+
+        assert!(exported.contains("dependencies = [\n]"));
+        assert!(exported.contains("[tool.uv]\npackage = true\n"));
+        assert!(exported.contains("[build-system]"));
+        assert!(exported
+            .contains("[tool.uv.sources]\nmypkg = {path=\"./libs/mypkg\", editable=true}\n"));
+        assert!(!exported.contains("path=\".\""));
     }
 
     #[test]
-    fn test_prepare_dev_dependencies() {
-        let packages = vec![
-            Package {
-                name: "requests".to_string(),
-                version: "==2.25.1".to_string(),
-                index: None,
-                extras: None,
-                is_dev: true,
+    fn test_export_project_metadata() {
+        let mut pipenv_content = PipenvContent {
+            options: ExportOptions {
+                project: ProjectMetadata {
+                    name: "service".to_string(),
+                    readme: Some("README.md".to_string()),
+                    authors: vec![Author {
+                        name: "Jane Doe".to_string(),
+                        email: Some("jane@example.org".to_string()),
+                    }],
+                    license: Some((Some("MIT".to_string()), "LICENSE".to_string())),
+                    urls: vec![(
+                        "Repository".to_string(),
+                        "https://github.com/org/service".to_string(),
+                    )],
+                    ..Default::default()
+                },
+                ..Default::default()
             },
-            Package {
-                name: "uvicorn".to_string(),
-                version: "==0.14.0".to_string(),
-                index: None,
-                extras: None,
-                is_dev: true,
+            ..empty_content()
+        };
+
+        let (project_lines, _, _) = pipenv_content._export_project_part(&mut Vec::new());
+
+        assert!(project_lines.starts_with("[project]\nname = \"service\"\nversion = \"0.1.0\"\n"));
+        assert!(project_lines.contains(
+            "readme = \"README.md\"\nlicense = \"MIT\"\nlicense-files = [\"LICENSE\"]\n"
+        ));
+        assert!(project_lines
+            .contains("authors = [\n\t{name = \"Jane Doe\", email = \"jane@example.org\"},\n]\n"));
+        assert!(project_lines
+            .ends_with("]\n\n[project.urls]\nRepository = \"https://github.com/org/service\"\n"));
+
+        pipenv_content.options.project.readme = Some("README".to_string());
+        let (project_lines, _, _) = pipenv_content._export_project_part(&mut Vec::new());
+        assert!(
+            project_lines.contains("readme = {file = \"README\", content-type = \"text/plain\"}\n")
+        );
+
+        pipenv_content.options.project.license = Some((None, "COPYING".to_string()));
+        let mut diagnostics = Vec::new();
+        let (project_lines, _, _) = pipenv_content._export_project_part(&mut diagnostics);
+        assert!(!project_lines.contains("license = "));
+        assert!(project_lines.contains("license-files = [\"COPYING\"]\n"));
+        assert_eq!(diagnostics.len(), 1);
+    }
+
+    #[test]
+    fn test_export_dynamic_version() {
+        let pipenv_content = PipenvContent {
+            options: ExportOptions {
+                project: ProjectMetadata {
+                    version: ProjectVersion::Attribute("service.__version__".to_string()),
+                    ..Default::default()
+                },
+                ..Default::default()
             },
-        ];
+            ..empty_content()
+        };
 
+        let exported = pipenv_content.export(&mut Vec::new());
+
+        assert!(exported.contains("dynamic = [\"version\"]\n"));
+        assert!(!exported.contains("\nversion = \""));
+        assert!(exported
+            .contains("[tool.setuptools.dynamic]\nversion = {attr = \"service.__version__\"}\n"));
+    }
+
+    #[test]
+    fn test_export_pep508_direct_reference() {
         let pipenv_content = PipenvContent {
-            packages,
-            pipenv: Pipenv {
-                python_version: "3.8".to_string(),
-                allow_prereleases: Some("true".to_string()),
+            packages: vec![Package {
+                source: Some(PackageSource::Url {
+                    url: "https://host/pkg-1.0.tar.gz".to_string(),
+                }),
+                ..package("pkg", "*", "packages")
+            }],
+            options: ExportOptions {
+                direct_references: DirectReferenceStyle::Pep508,
+                ..Default::default()
             },
-            sources: vec![],
+            ..empty_content()
         };
 
-        let packages_dup: Vec<&Package> = pipenv_content.packages.iter().collect();
+        let exported = pipenv_content.export(&mut Vec::new());
 
-        let lines = pipenv_content._prepare_dev_dependencies(packages_dup);
+        assert!(exported.contains("\t\"pkg @ https://host/pkg-1.0.tar.gz\",\n"));
+        assert!(!exported.contains("[tool.uv.sources]"));
+    }
+
+    #[test]
+    fn test_prepare_uv_settings_prerelease() {
+        let mut pipenv_content = empty_content();
+        pipenv_content.pipenv.allow_prereleases = Some(true);
 
         assert_eq!(
-            lines,
-            "[dependency-groups]\ndev = [\n\t\"requests==2.25.1\",\n\t\"uvicorn==0.14.0\",\n]\n"
+            pipenv_content._prepare_uv_settings(),
+            "[tool.uv]\nprerelease = \"allow\"\n"
+        );
+
+        pipenv_content.options.prerelease = PrereleasePolicy::IfNecessaryOrExplicit;
+        assert_eq!(
+            pipenv_content._prepare_uv_settings(),
+            "[tool.uv]\nprerelease = \"if-necessary-or-explicit\"\n"
         );
+
+        pipenv_content.pipenv.allow_prereleases = Some(false);
+        assert_eq!(pipenv_content._prepare_uv_settings(), "");
     }
 
     #[test]
-    fn test_prepare_sources() {
-        let sources = vec![Source {
-            name: "pypi".to_string(),
-            url: "https://pypi.org/simple".to_string(),
-            verify_ssl: Some("true".to_string()),
-        }];
+    fn test_prepare_uv_settings_insecure_host() {
+        let source = |name: &str, url: &str, verify_ssl: Option<bool>| Source {
+            name: name.to_string(),
+            url: url.to_string(),
+            verify_ssl,
+        };
 
         let pipenv_content = PipenvContent {
-            packages: vec![],
-            pipenv: Pipenv {
-                python_version: "3.8".to_string(),
-                allow_prereleases: Some("true".to_string()),
-            },
-            sources,
+            sources: vec![
+                source("pypi", "https://pypi.org/simple", Some(true)),
+                source("mirror", "https://mirror.corp/simple", Some(false)),
+                source(
+                    "mirror-extra",
+                    "https://mirror.corp/extra/simple",
+                    Some(false),
+                ),
+                source("default", "https://other.corp/simple", None),
+            ],
+            ..empty_content()
         };
-        let packages_dup: Vec<&Package> = pipenv_content.packages.iter().collect();
 
-        let lines = pipenv_content._prepare_sources(packages_dup);
+        assert_eq!(
+            pipenv_content._prepare_uv_settings(),
+            "[tool.uv]\nallow-insecure-host = [\"mirror.corp\"]\n"
+        );
+    }
 
-        assert_eq!(lines.lines().count(), 5);
+    #[test]
+    fn test_export_scripts() {
+        let script = |name: &str, command: ScriptCommand| Script {
+            name: name.to_string(),
+            command,
+        };
+        let mut pipenv_content = PipenvContent {
+            scripts: vec![
+                script("test", ScriptCommand::Command("pytest -x".to_string())),
+                script("serve", ScriptCommand::Call("app.main:run".to_string())),
+                script(
+                    "run app",
+                    ScriptCommand::Command("python app.py".to_string()),
+                ),
+            ],
+            ..empty_content()
+        };
+
+        assert!(pipenv_content.export(&mut Vec::new()).contains(
+            "[tool.poe.tasks]\ntest = \"pytest -x\"\nserve = {script = \"app.main:run\"}\n\"run app\" = \"python app.py\"\n"
+        ));
+        assert!(pipenv_content.scripts_file(&mut Vec::new()).is_none());
+
+        pipenv_content.options.scripts = ScriptStyle::Makefile;
+        assert!(!pipenv_content
+            .export(&mut Vec::new())
+            .contains("[tool.poe.tasks]"));
+        assert_eq!(
+            pipenv_content.scripts_file(&mut Vec::new()).unwrap(),
+            "# converted from the Pipfile [scripts]\n.PHONY: test serve\n\ntest:\n\tuv run pytest -x\n\nserve:\n\tuv run python -c 'import app.main; app.main.run()'\n"
+        );
+
+        pipenv_content.options.scripts = ScriptStyle::ProjectScripts;
+        let (project_lines, _, _) = pipenv_content._export_project_part(&mut Vec::new());
+        assert!(project_lines.ends_with("\n[project.scripts]\nserve = \"app.main:run\"\n"));
+        assert!(pipenv_content.is_package());
+        let exported = pipenv_content.export(&mut Vec::new());
+        assert!(exported.contains("[tool.uv]\npackage = true\n"));
+        assert!(exported.contains("[build-system]\n"));
+    }
+
+    #[test]
+    fn test_prepare_uv_settings_constraints() {
+        let pipenv_content = PipenvContent {
+            constraints: vec![
+                "certifi==2024.2.2".to_string(),
+                "pywin32==306; sys_platform == 'win32'".to_string(),
+            ],
+            ..empty_content()
+        };
+
+        assert_eq!(
+            pipenv_content._prepare_uv_settings(),
+            "[tool.uv]\nconstraint-dependencies = [\n\t\"certifi==2024.2.2\",\n\t\"pywin32==306; sys_platform == 'win32'\",\n]\n"
+        );
     }
 }
@@ -0,0 +1,211 @@
+use crate::models::toml_string;
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScriptCommand {
+    // command line run in the environment, e.g. `pytest -x`
+    Command(String),
+    // `{call = "package.module:function()"}`
+    Call(String),
+}
+
+/// An entry of the Pipfile `[scripts]` block
+#[derive(Clone, Debug, PartialEq)]
+pub struct Script {
+    pub name: String,
+    pub command: ScriptCommand,
+}
+
+/// Shell features that Pipenv never supported, poe runs them through a shell task
+fn needs_shell(command: &str) -> bool {
+    ["&&", "||", "|", ";", ">", "<", "$("]
+        .iter()
+        .any(|operator| command.contains(operator))
+}
+
+/// Splits `package.module:function(arguments)` into the module and the call expression
+fn split_call(call: &str) -> Option<(&str, String)> {
+    let (module, function) = call.trim().split_once(':')?;
+    if module.is_empty() || function.is_empty() {
+        return None;
+    }
+    let function: String = if function.ends_with(')') {
+        function.to_string()
+    } else {
+        format!("{}()", function)
+    };
+    Some((module, function))
+}
+
+impl Script {
+    /// Entry of `[tool.poe.tasks]`
+    pub fn as_poe_task(&self) -> String {
+        let key = toml_edit::Key::new(self.name.as_str());
+        match &self.command {
+            ScriptCommand::Command(command) if needs_shell(command) => {
+                format!("{} = {{shell = {}}}", key, toml_string(command))
+            }
+            ScriptCommand::Command(command) => format!("{} = {}", key, toml_string(command)),
+            ScriptCommand::Call(call) => format!("{} = {{script = {}}}", key, toml_string(call)),
+        }
+    }
+
+    /// Entry of `[project.scripts]`, only for calls of a function without arguments
+    pub fn as_entry_point(&self) -> Result<String, &'static str> {
+        let ScriptCommand::Call(call) = &self.command else {
+            return Err("only `call` scripts name an entry point");
+        };
+        let Some((module, function)) = split_call(call) else {
+            return Err("the call is not `module:function`");
+        };
+        let Some(function) = function.strip_suffix("()") else {
+            return Err("entry points take no arguments");
+        };
+        Ok(format!(
+            "{} = {}",
+            toml_edit::Key::new(self.name.as_str()),
+            toml_string(&format!("{}:{}", module, function))
+        ))
+    }
+
+    /// Command line run through `uv run`, make and just pass it to a shell
+    fn command_line(&self) -> Result<String, &'static str> {
+        match &self.command {
+            // every part of the pipeline has to run in the environment
+            ScriptCommand::Command(command) if needs_shell(command) => {
+                if command.contains('\'') {
+                    return Err("the command mixes shell operators and single quotes");
+                }
+                Ok(format!("uv run sh -c '{}'", command))
+            }
+            ScriptCommand::Command(command) => Ok(format!("uv run {}", command)),
+            ScriptCommand::Call(call) => {
+                let Some((module, function)) = split_call(call) else {
+                    return Err("the call is not `module:function`");
+                };
+                if call.contains('\'') {
+                    return Err("the call contains single quotes");
+                }
+                Ok(format!(
+                    "uv run python -c 'import {}; {}.{}'",
+                    module, module, function
+                ))
+            }
+        }
+    }
+
+    /// Makefile rule, `$` is doubled to reach the shell
+    pub fn as_make_rule(&self) -> Result<String, &'static str> {
+        if !self
+            .name
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || "_.-".contains(character))
+        {
+            return Err("the name is not a valid make target");
+        }
+        let command_line: String = self.command_line()?;
+        Ok(format!(
+            "{}:\n\t{}\n",
+            self.name,
+            command_line.replace('$', "$$")
+        ))
+    }
+
+    /// justfile recipe, extra arguments are passed on as with `pipenv run`
+    pub fn as_just_recipe(&self) -> Result<String, &'static str> {
+        let valid_start: bool = self
+            .name
+            .starts_with(|character: char| character.is_ascii_alphabetic() || character == '_');
+        if !valid_start
+            || !self
+                .name
+                .chars()
+                .all(|character| character.is_ascii_alphanumeric() || "_-".contains(character))
+        {
+            return Err("the name is not a valid just recipe");
+        }
+        let command_line: String = self.command_line()?.replace("{{", "{{{{");
+        match &self.command {
+            ScriptCommand::Command(command) if !needs_shell(command) => Ok(format!(
+                "{} *args:\n    {} {{{{args}}}}\n",
+                self.name, command_line
+            )),
+            _ => Ok(format!("{}:\n    {}\n", self.name, command_line)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn script(name: &str, command: ScriptCommand) -> Script {
+        Script {
+            name: name.to_string(),
+            command,
+        }
+    }
+
+    #[test]
+    fn test_as_poe_task() {
+        let test = script("test", ScriptCommand::Command("pytest -x".to_string()));
+        assert_eq!(test.as_poe_task(), "test = \"pytest -x\"");
+
+        let check = script(
+            "check",
+            ScriptCommand::Command("ruff check . && mypy".to_string()),
+        );
+        assert_eq!(
+            check.as_poe_task(),
+            "check = {shell = \"ruff check . && mypy\"}"
+        );
+
+        let serve = script("serve", ScriptCommand::Call("app.main:run".to_string()));
+        assert_eq!(serve.as_poe_task(), "serve = {script = \"app.main:run\"}");
+    }
+
+    #[test]
+    fn test_as_entry_point() {
+        let serve = script("serve", ScriptCommand::Call("app.main:run()".to_string()));
+        assert_eq!(serve.as_entry_point().unwrap(), "serve = \"app.main:run\"");
+
+        let seed = script("seed", ScriptCommand::Call("app.db:seed(10)".to_string()));
+        assert!(seed.as_entry_point().is_err());
+
+        let test = script("test", ScriptCommand::Command("pytest".to_string()));
+        assert!(test.as_entry_point().is_err());
+    }
+
+    #[test]
+    fn test_as_make_rule_and_just_recipe() {
+        let test = script(
+            "test",
+            ScriptCommand::Command("pytest $PYTEST_ARGS".to_string()),
+        );
+        assert_eq!(
+            test.as_make_rule().unwrap(),
+            "test:\n\tuv run pytest $$PYTEST_ARGS\n"
+        );
+        assert_eq!(
+            test.as_just_recipe().unwrap(),
+            "test *args:\n    uv run pytest $PYTEST_ARGS {{args}}\n"
+        );
+
+        let serve = script("serve", ScriptCommand::Call("app.main:run".to_string()));
+        assert_eq!(
+            serve.as_make_rule().unwrap(),
+            "serve:\n\tuv run python -c 'import app.main; app.main.run()'\n"
+        );
+
+        let check = script(
+            "check",
+            ScriptCommand::Command("ruff . && mypy".to_string()),
+        );
+        assert_eq!(
+            check.as_just_recipe().unwrap(),
+            "check:\n    uv run sh -c 'ruff . && mypy'\n"
+        );
+        let quoted = script("quoted", ScriptCommand::Call("app:greet('me')".to_string()));
+        assert!(quoted.as_just_recipe().is_err());
+        let named = script("1st", ScriptCommand::Command("true".to_string()));
+        assert!(named.as_just_recipe().is_err());
+    }
+}
@@ -0,0 +1,31 @@
+// Values shared by the model tests, each test then sets only the fields it checks
+
+use crate::models::options::ExportOptions;
+use crate::models::package::Package;
+use crate::models::pipenv::{Pipenv, PipenvRequirements};
+use crate::models::pipenv_content::PipenvContent;
+
+/// Index package of a Pipfile category, without extras, markers or a source
+pub fn package(name: &str, version: &str, category: &str) -> Package {
+    Package {
+        name: name.to_string(),
+        version: version.parse().unwrap(),
+        index: None,
+        extras: None,
+        source: None,
+        markers: None,
+        category: category.to_string(),
+    }
+}
+
+/// Content of an empty Pipfile converted with the default options
+pub fn empty_content() -> PipenvContent {
+    PipenvContent {
+        sources: vec![],
+        packages: vec![],
+        pipenv: Pipenv::new(),
+        constraints: vec![],
+        scripts: vec![],
+        options: ExportOptions::default(),
+    }
+}
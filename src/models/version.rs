@@ -0,0 +1,429 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PreReleaseLabel {
+    Alpha,
+    Beta,
+    ReleaseCandidate,
+}
+
+impl fmt::Display for PreReleaseLabel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PreReleaseLabel::Alpha => write!(f, "a"),
+            PreReleaseLabel::Beta => write!(f, "b"),
+            PreReleaseLabel::ReleaseCandidate => write!(f, "rc"),
+        }
+    }
+}
+
+/// A PEP 440 version, stored in its normalized form
+#[derive(Clone, Debug, PartialEq)]
+pub struct Version {
+    pub epoch: u64,
+    pub release: Vec<u64>,
+    pub pre: Option<(PreReleaseLabel, u64)>,
+    pub post: Option<u64>,
+    pub dev: Option<u64>,
+    pub local: Option<Vec<String>>,
+}
+
+impl Version {
+    pub fn is_prerelease(&self) -> bool {
+        self.pre.is_some() || self.dev.is_some()
+    }
+}
+
+/// Consumes an optional `-`, `_` or `.` separator
+fn skip_separator(input: &str) -> &str {
+    input.strip_prefix(['-', '_', '.']).unwrap_or(input)
+}
+
+/// Splits leading ASCII digits off the input
+fn take_number(input: &str) -> Option<(u64, &str)> {
+    let end: usize = input
+        .find(|character: char| !character.is_ascii_digit())
+        .unwrap_or(input.len());
+    if end == 0 {
+        return None;
+    }
+    let number: u64 = input[..end].parse().ok()?;
+    Some((number, &input[end..]))
+}
+
+/// Splits a label from `labels` off the input, longest spellings first
+fn take_label<'a>(input: &'a str, labels: &[&str]) -> Option<(String, &'a str)> {
+    labels
+        .iter()
+        .find(|label| input.starts_with(*label))
+        .map(|label| (label.to_string(), &input[label.len()..]))
+}
+
+fn parse_version_rest(mut rest: &str, version: &mut Version) -> Result<(), String> {
+    // pre-release: 1.0a1, 1.0-beta.2, 1.0rc
+    let candidate: &str = skip_separator(rest);
+    if let Some((label, after_label)) = take_label(
+        candidate,
+        &["alpha", "beta", "preview", "pre", "rc", "a", "b", "c"],
+    ) {
+        let label = match label.as_str() {
+            "a" | "alpha" => PreReleaseLabel::Alpha,
+            "b" | "beta" => PreReleaseLabel::Beta,
+            _ => PreReleaseLabel::ReleaseCandidate,
+        };
+        let after_label: &str = skip_separator(after_label);
+        let (number, after_number) = take_number(after_label).unwrap_or((0, after_label));
+        version.pre = Some((label, number));
+        rest = after_number;
+    }
+
+    // post-release: 1.0.post1, 1.0-r2, 1.0-1
+    let candidate: &str = skip_separator(rest);
+    if let Some((_, after_label)) = take_label(candidate, &["post", "rev", "r"]) {
+        let after_label: &str = skip_separator(after_label);
+        let (number, after_number) = take_number(after_label).unwrap_or((0, after_label));
+        version.post = Some(number);
+        rest = after_number;
+    } else if let Some(implicit) = rest.strip_prefix('-') {
+        if let Some((number, after_number)) = take_number(implicit) {
+            version.post = Some(number);
+            rest = after_number;
+        }
+    }
+
+    // development release: 1.0.dev3
+    let candidate: &str = skip_separator(rest);
+    if let Some((_, after_label)) = take_label(candidate, &["dev"]) {
+        let after_label: &str = skip_separator(after_label);
+        let (number, after_number) = take_number(after_label).unwrap_or((0, after_label));
+        version.dev = Some(number);
+        rest = after_number;
+    }
+
+    // local version: 1.0+ubuntu-1
+    if let Some(local) = rest.strip_prefix('+') {
+        let segments: Vec<String> = local
+            .split(['-', '_', '.'])
+            .map(|segment| segment.to_string())
+            .collect();
+        if segments.iter().any(|segment| {
+            segment.is_empty()
+                || !segment
+                    .chars()
+                    .all(|character| character.is_ascii_alphanumeric())
+        }) {
+            return Err(format!("invalid local version label '{}'", local));
+        }
+        version.local = Some(segments);
+        rest = "";
+    }
+
+    if !rest.is_empty() {
+        return Err(format!("unexpected '{}'", rest));
+    }
+    Ok(())
+}
+
+impl FromStr for Version {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let normalized: String = value.trim().to_lowercase();
+        let mut rest: &str = normalized.strip_prefix('v').unwrap_or(&normalized);
+
+        let mut epoch: u64 = 0;
+        if let Some((epoch_part, after_epoch)) = rest.split_once('!') {
+            epoch = epoch_part
+                .parse()
+                .map_err(|_| format!("invalid epoch in version '{}'", value))?;
+            rest = after_epoch;
+        }
+
+        let mut release: Vec<u64> = Vec::new();
+        loop {
+            let (number, after_number) = take_number(rest)
+                .ok_or_else(|| format!("invalid release segment in version '{}'", value))?;
+            release.push(number);
+            rest = after_number;
+
+            match rest.strip_prefix('.') {
+                Some(after_dot) if after_dot.starts_with(|c: char| c.is_ascii_digit()) => {
+                    rest = after_dot;
+                }
+                _ => break,
+            }
+        }
+
+        let mut version = Version {
+            epoch,
+            release,
+            pre: None,
+            post: None,
+            dev: None,
+            local: None,
+        };
+        parse_version_rest(rest, &mut version)
+            .map_err(|error| format!("invalid version '{}': {}", value, error))?;
+        Ok(version)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.epoch != 0 {
+            write!(f, "{}!", self.epoch)?;
+        }
+        let release: Vec<String> = self.release.iter().map(|n| n.to_string()).collect();
+        write!(f, "{}", release.join("."))?;
+        if let Some((label, number)) = &self.pre {
+            write!(f, "{}{}", label, number)?;
+        }
+        if let Some(post) = self.post {
+            write!(f, ".post{}", post)?;
+        }
+        if let Some(dev) = self.dev {
+            write!(f, ".dev{}", dev)?;
+        }
+        if let Some(local) = &self.local {
+            write!(f, "+{}", local.join("."))?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operator {
+    Compatible,
+    Equal,
+    NotEqual,
+    LessEqual,
+    GreaterEqual,
+    Less,
+    Greater,
+    ArbitraryEqual,
+}
+
+impl Operator {
+    // longer operators go first so that `===` is not read as `==`
+    const ALL: [(&'static str, Operator); 8] = [
+        ("===", Operator::ArbitraryEqual),
+        ("~=", Operator::Compatible),
+        ("==", Operator::Equal),
+        ("!=", Operator::NotEqual),
+        ("<=", Operator::LessEqual),
+        (">=", Operator::GreaterEqual),
+        ("<", Operator::Less),
+        (">", Operator::Greater),
+    ];
+
+    fn as_str(&self) -> &'static str {
+        Operator::ALL
+            .iter()
+            .find(|(_, operator)| operator == self)
+            .map(|(symbol, _)| *symbol)
+            .unwrap()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SpecifierVersion {
+    Version { version: Version, wildcard: bool },
+    // `===` compares plain strings and accepts anything
+    Arbitrary(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct VersionSpecifier {
+    pub operator: Operator,
+    pub version: SpecifierVersion,
+}
+
+impl VersionSpecifier {
+    pub fn new(operator: Operator, version: Version) -> Self {
+        VersionSpecifier {
+            operator,
+            version: SpecifierVersion::Version {
+                version,
+                wildcard: false,
+            },
+        }
+    }
+}
+
+impl FromStr for VersionSpecifier {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value: &str = value.trim();
+        let (operator, version_part) = match Operator::ALL
+            .iter()
+            .find(|(symbol, _)| value.starts_with(symbol))
+        {
+            Some((symbol, operator)) => (*operator, value[symbol.len()..].trim()),
+            // Pipenv accepts bare versions and treats them as exact pins
+            None => (Operator::Equal, value),
+        };
+
+        if operator == Operator::ArbitraryEqual {
+            if version_part.is_empty() || version_part.contains(char::is_whitespace) {
+                return Err(format!("invalid arbitrary version in '{}'", value));
+            }
+            return Ok(VersionSpecifier {
+                operator,
+                version: SpecifierVersion::Arbitrary(version_part.to_string()),
+            });
+        }
+
+        let (version_part, wildcard) = match version_part.strip_suffix(".*") {
+            Some(prefix) => (prefix, true),
+            None => (version_part, false),
+        };
+        let version: Version = version_part.parse()?;
+
+        if wildcard && !matches!(operator, Operator::Equal | Operator::NotEqual) {
+            return Err(format!(
+                "wildcard is only allowed with == and != in '{}'",
+                value
+            ));
+        }
+        let has_suffix: bool = version.pre.is_some()
+            || version.post.is_some()
+            || version.dev.is_some()
+            || version.local.is_some();
+        if wildcard && has_suffix {
+            return Err(format!(
+                "wildcard must follow the release segment in '{}'",
+                value
+            ));
+        }
+        if version.local.is_some() && !matches!(operator, Operator::Equal | Operator::NotEqual) {
+            return Err(format!(
+                "local versions are only allowed with == and != in '{}'",
+                value
+            ));
+        }
+        if operator == Operator::Compatible && version.release.len() < 2 {
+            return Err(format!(
+                "~= requires at least two release segments in '{}'",
+                value
+            ));
+        }
+
+        Ok(VersionSpecifier {
+            operator,
+            version: SpecifierVersion::Version { version, wildcard },
+        })
+    }
+}
+
+impl fmt::Display for VersionSpecifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.operator.as_str())?;
+        match &self.version {
+            SpecifierVersion::Version { version, wildcard } => {
+                write!(f, "{}", version)?;
+                if *wildcard {
+                    write!(f, ".*")?;
+                }
+                Ok(())
+            }
+            SpecifierVersion::Arbitrary(version) => write!(f, "{}", version),
+        }
+    }
+}
+
+/// Comma separated PEP 440 specifiers, empty when any version is accepted (`*`)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct VersionSpecifiers(pub Vec<VersionSpecifier>);
+
+impl VersionSpecifiers {
+    pub fn any() -> Self {
+        VersionSpecifiers(Vec::new())
+    }
+
+    pub fn is_any(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Whether a pre-release is requested explicitly, e.g. `==2.0rc1`
+    pub fn has_prerelease(&self) -> bool {
+        self.0.iter().any(|specifier| match &specifier.version {
+            SpecifierVersion::Version { version, .. } => {
+                specifier.operator != Operator::NotEqual && version.is_prerelease()
+            }
+            SpecifierVersion::Arbitrary(_) => false,
+        })
+    }
+}
+
+impl FromStr for VersionSpecifiers {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value: &str = value.trim();
+        if value.is_empty() || value == "*" {
+            return Ok(VersionSpecifiers::any());
+        }
+
+        let specifiers: Vec<VersionSpecifier> = value
+            .split(',')
+            .map(|specifier| specifier.parse())
+            .collect::<Result<_, _>>()?;
+        Ok(VersionSpecifiers(specifiers))
+    }
+}
+
+impl fmt::Display for VersionSpecifiers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let specifiers: Vec<String> = self.0.iter().map(|s| s.to_string()).collect();
+        write!(f, "{}", specifiers.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canonical(value: &str) -> String {
+        value.parse::<VersionSpecifiers>().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_canonical_specifiers() {
+        assert_eq!(canonical("*"), "");
+        assert_eq!(canonical("1.2"), "==1.2");
+        assert_eq!(canonical("~=1.4"), "~=1.4");
+        assert_eq!(canonical("!=2.0"), "!=2.0");
+        assert_eq!(canonical("==1.*"), "==1.*");
+        assert_eq!(canonical(">= 1.0RC1, < 2"), ">=1.0rc1,<2");
+        assert_eq!(canonical("==v1.0-beta.2"), "==1.0b2");
+        assert_eq!(canonical("==1.0-1"), "==1.0.post1");
+        assert_eq!(canonical("==1!2.0.dev"), "==1!2.0.dev0");
+        assert_eq!(canonical("==1.0+Ubuntu_1"), "==1.0+ubuntu.1");
+        assert_eq!(canonical("===foobar"), "===foobar");
+    }
+
+    #[test]
+    fn test_invalid_specifiers() {
+        assert!("~=1".parse::<VersionSpecifiers>().is_err());
+        assert!(">=1.*".parse::<VersionSpecifiers>().is_err());
+        assert!(">=1.0+local".parse::<VersionSpecifiers>().is_err());
+        assert!(">=1.0,".parse::<VersionSpecifiers>().is_err());
+        assert!("latest".parse::<VersionSpecifiers>().is_err());
+        assert!("==1.0.0garbage".parse::<VersionSpecifiers>().is_err());
+    }
+
+    #[test]
+    fn test_has_prerelease() {
+        let has_prerelease =
+            |value: &str| value.parse::<VersionSpecifiers>().unwrap().has_prerelease();
+
+        assert!(has_prerelease("==2.0rc1"));
+        assert!(has_prerelease(">=1.0.0b2,<2"));
+        assert!(has_prerelease("==3.0.dev4"));
+        assert!(!has_prerelease("!=2.0rc1"));
+        assert!(!has_prerelease(">=1.0.post1"));
+        assert!(!has_prerelease("*"));
+    }
+}
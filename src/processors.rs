@@ -1,203 +1,874 @@
-use crate::models::package::Package;
+use crate::errors::{ConversionError, Diagnostic};
+use crate::models::options::DuplicatePolicy;
+use crate::models::package::{canonicalize_name, GitReference, Package, PackageSource};
+use crate::models::pipenv::{Pipenv, PipenvRequirements};
+use crate::models::script::{Script, ScriptCommand};
 use crate::models::source::Source;
-use std::collections::HashMap;
+use crate::models::version::VersionSpecifiers;
+use std::ops::Range;
+use toml_edit::{Item, TableLike, Value};
 
-fn parse_to_hashmap(source_block: &Vec<String>) -> HashMap<String, String> {
-    let mut map = HashMap::new();
-    for line in source_block {
-        if line.trim().is_empty() {
-            continue;
-        }
+fn get_string(table: &dyn TableLike, key: &str) -> Option<String> {
+    table
+        .get(key)
+        .and_then(|item| item.as_str())
+        .map(|value| value.to_string())
+}
 
-        let parts: Vec<&str> = line.split('=').collect();
-        let key = parts[0].trim().to_string();
-        let value = parts[1].trim().trim_matches('"').to_string();
-        map.insert(key, value);
-    }
-    map
+pub fn parse_source_block(
+    source_block: &dyn TableLike,
+    span: Option<Range<usize>>,
+) -> Result<Source, Diagnostic> {
+    let Some(name) = get_string(source_block, "name") else {
+        return Err(Diagnostic::new("Source has no name", span));
+    };
+    let Some(url) = get_string(source_block, "url") else {
+        return Err(Diagnostic::new(
+            &format!("Source {} has no url", name),
+            span,
+        ));
+    };
+
+    Ok(Source {
+        name,
+        url,
+        verify_ssl: source_block
+            .get("verify_ssl")
+            .and_then(|item| item.as_bool()),
+    })
 }
 
-pub fn parse_source_block(source_block: &Vec<String>) -> Source {
-    let lines_map = parse_to_hashmap(source_block);
+fn parse_extras(extras_item: &Item) -> Option<Vec<String>> {
+    let extras: Vec<String> = extras_item
+        .as_array()?
+        .iter()
+        .filter_map(|extra| extra.as_str())
+        .map(|extra| extra.to_string())
+        .collect();
+    Some(extras)
+}
 
-    let name: String = lines_map.get("name").unwrap().trim_matches('"').to_string();
+fn parse_git_reference(package_data: &dyn TableLike) -> Option<GitReference> {
+    if let Some(branch) = get_string(package_data, "branch") {
+        return Some(GitReference::Branch(branch));
+    }
+    if let Some(tag) = get_string(package_data, "tag") {
+        return Some(GitReference::Tag(tag));
+    }
+    // Pipenv only knows a generic `ref`, which uv accepts as `rev`
+    get_string(package_data, "rev")
+        .or_else(|| get_string(package_data, "ref"))
+        .map(GitReference::Rev)
+}
 
-    Source {
-        name,
-        url: lines_map
-            .get("url")
-            .unwrap()
-            .clone()
-            .trim_matches('"')
-            .to_string(),
-        verify_ssl: lines_map.get("verify_ssl").cloned(),
+fn parse_package_source(package_data: &dyn TableLike) -> Option<PackageSource> {
+    if let Some(git_url) = get_string(package_data, "git") {
+        return Some(PackageSource::Git {
+            url: git_url.trim_start_matches("git+").to_string(),
+            reference: parse_git_reference(package_data),
+            subdirectory: get_string(package_data, "subdirectory"),
+        });
     }
+    if let Some(path) = get_string(package_data, "path") {
+        return Some(PackageSource::Path {
+            path,
+            editable: package_data
+                .get("editable")
+                .and_then(|item| item.as_bool())
+                .unwrap_or(false),
+        });
+    }
+    if let Some(file) = get_string(package_data, "file") {
+        if file.contains("://") && !file.starts_with("file://") {
+            return Some(PackageSource::Url { url: file });
+        }
+        return Some(PackageSource::Path {
+            path: file.trim_start_matches("file://").to_string(),
+            editable: false,
+        });
+    }
+    None
 }
 
-fn parse_package(package_line: &str, is_dev: bool) -> Package {
-    let split: Option<(&str, &str)> = package_line.split_once('=');
+/// Marker variables Pipenv accepts as shorthand keys inside a package table
+const MARKER_KEYS: [&str; 11] = [
+    "os_name",
+    "sys_platform",
+    "platform_machine",
+    "platform_python_implementation",
+    "platform_release",
+    "platform_system",
+    "platform_version",
+    "python_version",
+    "python_full_version",
+    "implementation_name",
+    "implementation_version",
+];
 
-    let sp2 = split.unwrap_or(("", ""));
+fn parse_markers(package_data: &dyn TableLike) -> Option<String> {
+    let mut markers: Vec<String> = Vec::new();
+
+    if let Some(explicit_markers) = get_string(package_data, "markers") {
+        markers.push(explicit_markers.trim().to_string());
+    }
 
-    if sp2.0.trim().is_empty() {
-        return Package {
-            name: sp2.1.trim().to_string(),
-            version: "".to_string(),
+    for (key, value) in package_data.iter() {
+        if !MARKER_KEYS.contains(&key) {
+            continue;
+        }
+        let Some(condition) = value.as_str().map(|condition| condition.trim()) else {
+            continue;
+        };
+        if condition.starts_with(['=', '!', '<', '>', '~']) || condition.starts_with("not in") {
+            markers.push(format!("{} {}", key, condition));
+        } else if let Some(members) = condition.strip_prefix("in ") {
+            markers.push(format!("{} in {}", key, members.trim()));
+        } else {
+            markers.push(format!("{} == '{}'", key, condition.trim_matches('\'')));
+        }
+    }
+
+    match markers.len() {
+        0 => None,
+        1 => markers.pop(),
+        _ => Some(
+            markers
+                .iter()
+                .map(|marker| {
+                    if marker.contains(" or ") {
+                        format!("({})", marker)
+                    } else {
+                        marker.clone()
+                    }
+                })
+                .collect::<Vec<String>>()
+                .join(" and "),
+        ),
+    }
+}
+
+fn parse_version(
+    package_name: &str,
+    version: &str,
+    span: Option<Range<usize>>,
+) -> Result<VersionSpecifiers, Diagnostic> {
+    version.parse().map_err(|error| {
+        Diagnostic::new(
+            &format!("Invalid version of package {}: {}", package_name, error),
+            span,
+        )
+    })
+}
+
+fn parse_package(
+    package_name: &str,
+    package_data: &Item,
+    category: &str,
+) -> Result<Package, Diagnostic> {
+    if let Some(version) = package_data.as_str() {
+        return Ok(Package {
+            name: package_name.to_string(),
+            version: parse_version(package_name, version, package_data.span())?,
             index: None,
             extras: None,
-            is_dev,
-        };
+            source: None,
+            markers: None,
+            category: category.to_string(),
+        });
     }
 
-    let package_name: &str = split.unwrap().0.trim();
-    let package_version: &str = split.unwrap().1.trim();
+    let Some(extended_package_data) = package_data.as_table_like() else {
+        return Err(Diagnostic::new(
+            &format!("Unsupported specification for package {}", package_name),
+            package_data.span(),
+        ));
+    };
 
-    let extended_package_data: &str;
+    let version_item: Option<&Item> = extended_package_data.get("version");
+    Ok(Package {
+        name: package_name.to_string(),
+        version: parse_version(
+            package_name,
+            version_item.and_then(|item| item.as_str()).unwrap_or("*"),
+            version_item.and_then(|item| item.span()),
+        )?,
+        index: get_string(extended_package_data, "index"),
+        extras: extended_package_data.get("extras").and_then(parse_extras),
+        source: parse_package_source(extended_package_data),
+        markers: parse_markers(extended_package_data),
+        category: category.to_string(),
+    })
+}
+
+/// Parses every package of a category, recording the broken ones instead of stopping
+pub fn parse_packages_block(
+    packages_block: &dyn TableLike,
+    category: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Vec<Package> {
+    let mut packages = Vec::new();
 
-    if package_version.starts_with('{') {
-        extended_package_data = package_version
-            .trim_start_matches('{')
-            .trim_end_matches('}')
-            .trim();
+    for (package_name, package_data) in packages_block.iter() {
+        match parse_package(package_name, package_data, category) {
+            Ok(package) => packages.push(package),
+            Err(diagnostic) => diagnostics.push(diagnostic),
+        }
+    }
+    packages
+}
 
-        let version_regex = regex::Regex::new(r#"version\s?=\s?"([\d<>=,.*]+)""#).unwrap();
+fn parse_sources_block(sources_block: &Item, diagnostics: &mut Vec<Diagnostic>) -> Vec<Source> {
+    let results: Vec<Result<Source, Diagnostic>> = match sources_block {
+        Item::ArrayOfTables(tables) => tables
+            .iter()
+            .map(|t| parse_source_block(t, t.span()))
+            .collect(),
+        Item::Value(Value::Array(array)) => array
+            .iter()
+            .filter_map(|value| value.as_inline_table())
+            .map(|t| parse_source_block(t, t.span()))
+            .collect(),
+        _ => vec![Err(Diagnostic::new(
+            "Unsupported source block format, expected [[source]] tables",
+            sources_block.span(),
+        ))],
+    };
 
-        let version: &str;
-        if let Some(caps) = version_regex.captures(extended_package_data) {
-            version = caps.get(1).unwrap().as_str().trim_matches('"');
-        } else {
-            version = package_version;
+    let mut sources: Vec<Source> = Vec::new();
+    for result in results {
+        match result {
+            Ok(source) => sources.push(source),
+            Err(diagnostic) => diagnostics.push(diagnostic),
         }
+    }
+    sources
+}
 
-        let index_regex = regex::Regex::new(r#"index\s?=\s?"(\w+)""#).unwrap();
-        let index: Option<String>;
-        if let Some(caps) = index_regex.captures(extended_package_data) {
-            index = Some(caps.get(1).unwrap().as_str().trim_matches('"').to_string());
-        } else {
-            index = None;
-        }
-
-        let extras: Option<Vec<String>>;
-        let extras_regex = regex::Regex::new(r#"extras\s?=\s?\[(["\w,]+)]"#).unwrap();
-        if let Some(caps) = extras_regex.captures(extended_package_data) {
-            extras = Some(
-                caps.get(1)
-                    .unwrap()
-                    .as_str()
-                    .split(',')
-                    .map(|s| s.trim_matches('"').to_string())
-                    .collect(),
-            );
-        } else {
-            extras = None;
+/// Blanks a line while keeping the byte offsets of the rest of the content
+fn blank_line(content: &mut String, offset: usize) -> bool {
+    let offset: usize = offset.min(content.len());
+    let line_start: usize = content[..offset].rfind('\n').map_or(0, |index| index + 1);
+    let line_end: usize = content[offset..]
+        .find('\n')
+        .map_or(content.len(), |index| offset + index);
+    let line: &str = &content[line_start..line_end];
+    if line.trim().is_empty() {
+        return false;
+    }
+    let blank: String = " ".repeat(line.len());
+    content.replace_range(line_start..line_end, &blank);
+    true
+}
+
+/// Blanks a broken table header together with the entries below it, which
+/// would otherwise be read as entries of the table before
+fn blank_table(content: &mut String, offset: usize) -> bool {
+    let offset: usize = offset.min(content.len());
+    let line_start: usize = content[..offset].rfind('\n').map_or(0, |index| index + 1);
+    if !content[line_start..].trim_start().starts_with('[') {
+        return false;
+    }
+    let mut line_end: usize = line_start;
+    for (index, line) in content[line_start..].split_inclusive('\n').enumerate() {
+        if index > 0 && line.trim_start().starts_with('[') {
+            break;
         }
+        line_end += line.len();
+    }
+    let blank: String = content[line_start..line_end]
+        .chars()
+        .map(|character| if character == '\n' { '\n' } else { ' ' })
+        .collect();
+    content.replace_range(line_start..line_end, &blank);
+    true
+}
 
-        Package {
-            name: package_name.to_string(),
-            version: version.to_string(),
-            index,
-            extras,
-            is_dev,
+/// Parses a TOML document, dropping every line with a syntax error so later
+/// problems are found in the same run, a broken table header drops its whole table
+pub fn parse_document_recovering(
+    content: &str,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> toml_edit::Document<String> {
+    let mut recovered: String = content.to_string();
+    loop {
+        match toml_edit::Document::parse(recovered.clone()) {
+            Ok(document) => return document,
+            Err(error) => {
+                let mut diagnostic: Diagnostic = error.into();
+                let dropped: bool = match diagnostic.span.clone() {
+                    Some(span) if blank_table(&mut recovered, span.start) => {
+                        diagnostic.message.push_str(", the table is left out");
+                        true
+                    }
+                    Some(span) => blank_line(&mut recovered, span.start),
+                    None => false,
+                };
+                diagnostics.push(diagnostic);
+                if !dropped {
+                    return toml_edit::Document::parse(String::new())
+                        .expect("An empty document is valid TOML");
+                }
+            }
         }
-    } else {
-        let package: Package = Package {
-            name: package_name.to_string(),
-            version: package_version.to_string().trim_matches('"').to_string(),
-            index: None,
-            extras: None,
-            is_dev,
+    }
+}
+
+/// `name = "command"` and `name = {call = "module:function()"}` entries of [scripts]
+fn parse_scripts_block(scripts_block: &Item, diagnostics: &mut Vec<Diagnostic>) -> Vec<Script> {
+    let Some(scripts_block) = scripts_block.as_table_like() else {
+        diagnostics.push(Diagnostic::new(
+            "Unsupported scripts block format, expected a [scripts] table",
+            scripts_block.span(),
+        ));
+        return Vec::new();
+    };
+
+    let mut scripts: Vec<Script> = Vec::new();
+    for (name, item) in scripts_block.iter() {
+        let command = match (item.as_str(), item.as_table_like()) {
+            (Some(command), _) => Some(ScriptCommand::Command(command.to_string())),
+            (None, Some(table)) => get_string(table, "call").map(ScriptCommand::Call),
+            _ => None,
         };
-        package
+        match command {
+            Some(command) => scripts.push(Script {
+                name: name.to_string(),
+                command,
+            }),
+            None => diagnostics.push(Diagnostic::new(
+                &format!("Unsupported specification for script {}", name),
+                item.span(),
+            )),
+        }
     }
+    scripts
 }
 
-pub fn parse_packages_block(packages_block: &Vec<String>, is_dev: bool) -> Vec<Package> {
-    let mut packages = Vec::new();
+pub fn parse_pipenv_settings(requires_block: Option<&Item>, pipenv_block: Option<&Item>) -> Pipenv {
+    let mut pipenv: Pipenv = PipenvRequirements::new();
 
-    for line in packages_block {
-        if line.starts_with('#') || line.trim().is_empty() {
-            // this is a comment or empty line
+    if let Some(requires) = requires_block.and_then(|item| item.as_table_like()) {
+        // python_full_version is only used when no short version is stated
+        let python_version = get_string(requires, "python_version")
+            .or_else(|| get_string(requires, "python_full_version"));
+        if let Some(python_version) = python_version {
+            pipenv.set_py_version(&python_version);
+        }
+    }
+
+    if let Some(settings) = pipenv_block.and_then(|item| item.as_table_like()) {
+        if let Some(allow_prereleases) = settings
+            .get("allow_prereleases")
+            .and_then(|item| item.as_bool())
+        {
+            pipenv.set_prereleases_status(allow_prereleases);
+        }
+    }
+
+    pipenv
+}
+
+fn has_location(package: &Package) -> bool {
+    package.index.is_some() || package.source.is_some()
+}
+
+/// Two declarations can be merged when they only differ in specifiers and extras
+fn can_merge(package: &Package, other: &Package) -> bool {
+    package.markers == other.markers && !(has_location(package) && has_location(other))
+}
+
+fn merge_package(package: &mut Package, other: Package) {
+    package.version.0.extend(other.version.0);
+    if let Some(other_extras) = other.extras {
+        let extras: &mut Vec<String> = package.extras.get_or_insert_with(Vec::new);
+        for extra in other_extras {
+            if !extras.contains(&extra) {
+                extras.push(extra);
+            }
+        }
+    }
+    if !has_location(package) {
+        package.index = other.index;
+        package.source = other.source;
+    }
+}
+
+/// Finds packages declared more than once under PEP 503 equivalent names
+pub fn resolve_duplicates(
+    packages: Vec<Package>,
+    policy: DuplicatePolicy,
+    normalize_names: bool,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<Vec<Package>, ConversionError> {
+    let mut resolved: Vec<Package> = Vec::new();
+
+    for mut package in packages {
+        let declared_name: String = package.name.clone();
+        let canonical_name: String = canonicalize_name(&package.name);
+        if normalize_names {
+            package.name = canonical_name.clone();
+        }
+
+        let duplicate: Option<usize> = resolved
+            .iter()
+            .position(|known| canonicalize_name(&known.name) == canonical_name);
+        let Some(position) = duplicate else {
+            resolved.push(package);
             continue;
+        };
+
+        let known: &mut Package = &mut resolved[position];
+        let description: String = format!(
+            "{} in [{}] duplicates {} in [{}]",
+            declared_name, package.category, known.name, known.category
+        );
+        match policy {
+            DuplicatePolicy::Report => {
+                diagnostics.push(Diagnostic::warning(format!("Package {}", description)));
+                resolved.push(package);
+            }
+            DuplicatePolicy::Reject => {
+                return Err(ConversionError::DuplicatePackage(description));
+            }
+            DuplicatePolicy::Merge if known.category != package.category => {
+                if known.is_default() {
+                    // main dependencies are installed together with every group
+                    diagnostics.push(Diagnostic::info(format!(
+                        "Package {}, dropping it",
+                        description
+                    )));
+                } else {
+                    diagnostics.push(Diagnostic::warning(format!(
+                        "Package {}, keeping both",
+                        description
+                    )));
+                    resolved.push(package);
+                }
+            }
+            DuplicatePolicy::Merge if can_merge(known, &package) => {
+                diagnostics.push(Diagnostic::info(format!(
+                    "Package {}, merging them",
+                    description
+                )));
+                merge_package(known, package);
+            }
+            DuplicatePolicy::Merge => {
+                diagnostics.push(Diagnostic::warning(format!(
+                    "Package {} and cannot be merged",
+                    description
+                )));
+                resolved.push(package);
+            }
         }
-        let package = parse_package(line, is_dev);
-        packages.push(package);
     }
-    packages
+
+    Ok(resolved)
 }
 
 pub enum BufferResultEnum<A, B> {
-    Source(A),
+    Sources(A),
     Packages(B),
+    Scripts(Vec<Script>),
     SkippedBlock, // Used when a block is not processed
     Unknown,
 }
 
-pub fn process_previous_buffer(
+pub fn process_block(
     block_name: &str,
-    line_buffer: &Vec<String>,
-) -> BufferResultEnum<Source, Vec<Package>> {
+    block: &Item,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> BufferResultEnum<Vec<Source>, Vec<Package>> {
     match block_name {
-        "source" => {
-            let source_block = parse_source_block(line_buffer);
-            BufferResultEnum::Source(source_block)
-        }
+        "source" => BufferResultEnum::Sources(parse_sources_block(block, diagnostics)),
         "pipenv" => BufferResultEnum::SkippedBlock,
         "requires" => BufferResultEnum::SkippedBlock,
-        "packages" => {
-            let packages = parse_packages_block(line_buffer, false);
-            BufferResultEnum::Packages(packages)
-        }
-        "dev-packages" => {
-            // println!("Processing dev-packages block");
-            let packages = parse_packages_block(line_buffer, true);
-            BufferResultEnum::Packages(packages)
-        }
-        _ => {
-            println!("Unknown block: {}", block_name);
-            BufferResultEnum::Unknown
-        }
+        "scripts" => BufferResultEnum::Scripts(parse_scripts_block(block, diagnostics)),
+        // every other table is a package category, e.g. [packages], [dev-packages] or [docs]
+        _ => match block.as_table_like() {
+            Some(packages_block) => BufferResultEnum::Packages(parse_packages_block(
+                packages_block,
+                block_name,
+                diagnostics,
+            )),
+            None => {
+                diagnostics.push(Diagnostic::info(format!("Unknown block: {}", block_name)));
+                BufferResultEnum::Unknown
+            }
+        },
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::package::DEFAULT_CATEGORY;
+    use toml_edit::DocumentMut;
+
+    fn parse_document(content: &str) -> DocumentMut {
+        content.parse::<DocumentMut>().unwrap()
+    }
 
     #[test]
     fn test_parse_source_block() {
-        let source_block = vec![
-            String::from("name = \"pypi\""),
-            String::from("url = \"https://pypi.org/simple\""),
-            String::from("verify_ssl = \"true\""),
-        ];
+        let document = parse_document(
+            r#"
+[[source]]
+name = "pypi"
+url = "https://pypi.org/simple"
+verify_ssl = true
+"#,
+        );
 
-        let source = parse_source_block(&source_block);
+        let sources = parse_sources_block(document.get("source").unwrap(), &mut Vec::new());
 
-        assert_eq!(source.name, "pypi");
-        assert_eq!(source.url, "https://pypi.org/simple");
-        assert_eq!(source.verify_ssl.unwrap(), "true");
+        assert_eq!(sources.len(), 1);
+        assert_eq!(sources[0].name, "pypi");
+        assert_eq!(sources[0].url, "https://pypi.org/simple");
+        assert!(sources[0].verify_ssl.unwrap());
     }
 
     #[test]
     fn test_parse_package() {
-        let package_line = String::from("requests = {version=\">=2.25.1\", extras=[socks]}");
+        let document = parse_document(r#"requests = {version=">=2.25.1", extras=["socks"]}"#);
 
-        let package = parse_package(&package_line, false);
+        let package = parse_package(
+            "requests",
+            document.get("requests").unwrap(),
+            DEFAULT_CATEGORY,
+        )
+        .unwrap();
 
         assert_eq!(package.name, "requests");
-        assert_eq!(package.version, ">=2.25.1");
+        assert_eq!(package.version.to_string(), ">=2.25.1");
         assert_eq!(package.extras.unwrap(), vec!["socks".to_string()]);
     }
 
     #[test]
     fn test_parse_packages_block() {
-        let packages_block = vec![
-            String::from("requests = \">=2.25.1\""),
-            String::from("requests = {version=\">=2.25.1\", extras=[socks]}"),
-        ];
+        let document = parse_document(
+            r#"
+[packages]  # comment after a header
+requests = ">=2.25.1"
+"zope.interface" = "*"
+httpx = {version="==0.27.0", index="internal"}
+pyjwt = {
+    version = ">=2.0",
+    extras = ["crypto"],
+}
+"#,
+        );
+
+        let packages = parse_packages_block(
+            document["packages"].as_table().unwrap(),
+            DEFAULT_CATEGORY,
+            &mut Vec::new(),
+        );
+
+        assert_eq!(packages.len(), 4);
+        assert_eq!(packages[1].name, "zope.interface");
+        assert_eq!(packages[2].index.clone().unwrap(), "internal");
+        assert_eq!(packages[3].version.to_string(), ">=2.0");
+    }
+
+    #[test]
+    fn test_process_custom_category() {
+        let document = parse_document(
+            r#"
+[docs]
+sphinx = "*"
+"#,
+        );
+
+        match process_block("docs", document.get("docs").unwrap(), &mut Vec::new()) {
+            BufferResultEnum::Packages(packages) => {
+                assert_eq!(packages.len(), 1);
+                assert_eq!(packages[0].category, "docs");
+            }
+            _ => panic!("Custom category was not parsed as packages"),
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_version() {
+        let document = parse_document(
+            r#"
+[packages]
+broken = "latest"
+requests = "2.25.1"
+"#,
+        );
+
+        let mut diagnostics = Vec::new();
+        let packages = parse_packages_block(
+            document["packages"].as_table().unwrap(),
+            "packages",
+            &mut diagnostics,
+        );
+
+        assert_eq!(packages.len(), 1);
+        assert_eq!(packages[0].version.to_string(), "==2.25.1");
+        assert_eq!(diagnostics.len(), 1);
+        assert!(diagnostics[0]
+            .message
+            .starts_with("Invalid version of package broken"));
+    }
+
+    #[test]
+    fn test_parse_git_package() {
+        let document = parse_document(
+            r#"mylib = {git = "git+https://github.com/org/mylib.git", ref = "v1.2", subdirectory = "pkg"}"#,
+        );
+
+        let package =
+            parse_package("mylib", document.get("mylib").unwrap(), DEFAULT_CATEGORY).unwrap();
+
+        assert!(package.version.is_any());
+        match package.source {
+            Some(PackageSource::Git {
+                url,
+                reference: Some(GitReference::Rev(rev)),
+                subdirectory,
+            }) => {
+                assert_eq!(url, "https://github.com/org/mylib.git");
+                assert_eq!(rev, "v1.2");
+                assert_eq!(subdirectory.unwrap(), "pkg");
+            }
+            _ => panic!("Git source was not recognised"),
+        }
+    }
+
+    #[test]
+    fn test_parse_package_markers() {
+        let document = parse_document(
+            r#"
+[packages]
+uvloop = {version = "*", markers = "python_version >= '3.8' or implementation_name == 'pypy'", sys_platform = "!= 'win32'"}
+pywin32 = {version = "*", sys_platform = "== 'win32'"}
+"#,
+        );
+
+        let packages = parse_packages_block(
+            document["packages"].as_table().unwrap(),
+            DEFAULT_CATEGORY,
+            &mut Vec::new(),
+        );
+
+        assert_eq!(
+            packages[0].markers.clone().unwrap(),
+            "(python_version >= '3.8' or implementation_name == 'pypy') and sys_platform != 'win32'"
+        );
+        assert_eq!(
+            packages[1].markers.clone().unwrap(),
+            "sys_platform == 'win32'"
+        );
+    }
+
+    #[test]
+    fn test_parse_file_package() {
+        let document = parse_document(
+            r#"
+[packages]
+remote = {file = "https://host/remote-1.0.tar.gz"}
+vendored = {file = "./wheels/vendored-2.0-py3-none-any.whl"}
+"#,
+        );
+
+        let packages = parse_packages_block(
+            document["packages"].as_table().unwrap(),
+            DEFAULT_CATEGORY,
+            &mut Vec::new(),
+        );
+
+        match &packages[0].source {
+            Some(PackageSource::Url { url }) => assert_eq!(url, "https://host/remote-1.0.tar.gz"),
+            _ => panic!("Url source was not recognised"),
+        }
+        match &packages[1].source {
+            Some(PackageSource::Path { path, editable }) => {
+                assert_eq!(path, "./wheels/vendored-2.0-py3-none-any.whl");
+                assert!(!editable);
+            }
+            _ => panic!("File source was not recognised"),
+        }
+    }
+
+    #[test]
+    fn test_parse_path_package() {
+        let document = parse_document(r#""." = {editable = true, path = "."}"#);
+
+        let package = parse_package(".", document.get(".").unwrap(), DEFAULT_CATEGORY).unwrap();
+
+        assert!(package.is_project_itself());
+        match package.source {
+            Some(PackageSource::Path { path, editable }) => {
+                assert_eq!(path, ".");
+                assert!(editable);
+            }
+            _ => panic!("Path source was not recognised"),
+        }
+    }
+
+    #[test]
+    fn test_parse_source_url_with_query() {
+        let document = parse_document(
+            r#"
+[[source]]
+name = "internal"
+url = "https://mirror.local/simple?token=abc=="
+verify_ssl = false
+"#,
+        );
+
+        let sources = parse_sources_block(document.get("source").unwrap(), &mut Vec::new());
+
+        assert_eq!(sources[0].url, "https://mirror.local/simple?token=abc==");
+        assert!(!sources[0].verify_ssl.unwrap());
+    }
+
+    #[test]
+    fn test_parse_source_without_url() {
+        let content = "[packages]\nrequests = \"*\"\n\n[[source]]\nname = \"pypi\"\n";
+        let document = toml_edit::Document::parse(content).unwrap();
+
+        let mut diagnostics = Vec::new();
+        let sources = parse_sources_block(document.get("source").unwrap(), &mut diagnostics);
+        let diagnostic = &diagnostics[0];
+
+        assert!(sources.is_empty());
+        assert_eq!(diagnostic.message, "Source pypi has no url");
+        assert_eq!(diagnostic.location(content), Some((4, 1)));
+    }
+
+    #[test]
+    fn test_parse_document_recovering() {
+        let content = "[packages]\nrequests = \"*\nflask = >=2\ndjango = \"*\"\n";
+        let mut diagnostics = Vec::new();
+
+        let document = parse_document_recovering(content, &mut diagnostics);
+
+        let mut lines: Vec<usize> = diagnostics
+            .iter()
+            .map(|diagnostic| diagnostic.location(content).unwrap().0)
+            .collect();
+        lines.sort();
+        assert_eq!(lines, vec![2, 3]);
+        assert!(document["packages"].get("django").is_some());
+        assert!(document["packages"].get("requests").is_none());
+
+        let content = "[packages]\nrequests = \"*\"\n\n[dev-packages]\npytest = \"*\"\n\n[packages]\nflask = \"*\"\n\n[scripts]\ntest = \"pytest\"\n";
+        let mut diagnostics = Vec::new();
+
+        let document = parse_document_recovering(content, &mut diagnostics);
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].location(content).unwrap().0, 7);
+        assert!(diagnostics[0].message.ends_with(", the table is left out"));
+        // the entries of the duplicate table do not move into [dev-packages]
+        assert!(document["dev-packages"].get("flask").is_none());
+        assert!(document["packages"].get("requests").is_some());
+        assert!(document["scripts"].get("test").is_some());
+    }
+
+    #[test]
+    fn test_parse_pipenv_settings() {
+        let document = parse_document(
+            r#"
+[requires]
+python_version = "3.11"
+
+[pipenv]
+allow_prereleases = true
+"#,
+        );
+
+        let pipenv = parse_pipenv_settings(document.get("requires"), document.get("pipenv"));
+
+        assert_eq!(pipenv.python_version, "3.11");
+        assert!(pipenv.allow_prereleases.unwrap());
+    }
+
+    #[test]
+    fn test_parse_scripts_block() {
+        let document = parse_document(
+            r#"
+[scripts]
+test = "pytest -x"
+serve = {call = "app.main:run()"}
+broken = 1
+"#,
+        );
+        let mut diagnostics: Vec<Diagnostic> = Vec::new();
+
+        let scripts = parse_scripts_block(&document["scripts"], &mut diagnostics);
+
+        assert_eq!(scripts.len(), 2);
+        assert_eq!(
+            scripts[0].command,
+            ScriptCommand::Command("pytest -x".to_string())
+        );
+        assert_eq!(
+            scripts[1].command,
+            ScriptCommand::Call("app.main:run()".to_string())
+        );
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "Unsupported specification for script broken"
+        );
+    }
+
+    #[test]
+    fn test_resolve_duplicates() {
+        let document = parse_document(
+            r#"
+[packages]
+Django = ">=4"
+django = {version = "<5", extras = ["bcrypt"]}
+typing_extensions = "*"
+
+[dev-packages]
+typing-extensions = "*"
+pytest = "*"
+"#,
+        );
+        let parse = || {
+            let mut packages = parse_packages_block(
+                document["packages"].as_table().unwrap(),
+                "packages",
+                &mut Vec::new(),
+            );
+            packages.extend(parse_packages_block(
+                document["dev-packages"].as_table().unwrap(),
+                "dev-packages",
+                &mut Vec::new(),
+            ));
+            packages
+        };
+
+        let mut diagnostics: Vec<Diagnostic> = Vec::new();
+        let reported =
+            resolve_duplicates(parse(), DuplicatePolicy::Report, false, &mut diagnostics).unwrap();
+        assert_eq!(reported.len(), 5);
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(
+            diagnostics[0].message,
+            "Package django in [packages] duplicates Django in [packages]"
+        );
 
-        let packages = parse_packages_block(&packages_block, false);
+        assert!(
+            resolve_duplicates(parse(), DuplicatePolicy::Reject, false, &mut Vec::new()).is_err()
+        );
 
-        assert_eq!(packages.len(), 2);
+        let merged =
+            resolve_duplicates(parse(), DuplicatePolicy::Merge, true, &mut Vec::new()).unwrap();
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[0].name, "django");
+        assert_eq!(merged[0].version.to_string(), ">=4,<5");
+        assert_eq!(
+            merged[0].extras.clone().unwrap(),
+            vec!["bcrypt".to_string()]
+        );
+        assert_eq!(merged[1].name, "typing-extensions");
+        assert_eq!(merged[2].name, "pytest");
     }
 }
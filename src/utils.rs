@@ -1,37 +1,42 @@
+use pipenv2uv::info;
 use std::path::{Path, PathBuf};
 
-pub fn get_output_file_name(is_docker: bool) -> String {
-    let mut output_path = PathBuf::from("pyproject.toml");
+pub const OUTPUT_FILE_NAME: &str = "pyproject.toml";
 
+/// Output path requested by the user, a directory gets pyproject.toml inside it
+pub fn requested_output_path(output: Option<&Path>, input: &Path) -> PathBuf {
+    match output {
+        Some(output) if output.is_dir() || output.to_string_lossy().ends_with(['/', '\\']) => {
+            output.join(OUTPUT_FILE_NAME)
+        }
+        Some(output) => output.to_path_buf(),
+        None => input.with_file_name(OUTPUT_FILE_NAME),
+    }
+}
+
+/// Path to write to, existing files are only replaced with `force`
+pub fn get_output_file_name(requested_path: &Path, force: bool) -> PathBuf {
+    let mut output_path = requested_path.to_path_buf();
+    if force {
+        return output_path;
+    }
+
+    let stem = requested_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or("pyproject".to_string());
     let mut counter = 1;
 
-    if is_docker {
-        // when we work via docker export files are created in special directory
-        // also the file is overwritten
-        let parent_path = Path::new("output/");
-        output_path = parent_path.join(output_path);
-
-        while output_path.exists() {
-            println!(
-                "File {} already exists, creating new",
-                output_path.to_str().unwrap()
-            );
-            let filename = format!("{}-new-{}.toml", "pyproject", counter);
-            counter += 1;
-            output_path = parent_path.join(filename);
-        }
-    } else {
-        while output_path.exists() {
-            println!(
-                "File {} already exists, creating new",
-                output_path.clone().to_str().unwrap()
-            );
-            let filename = format!("{}-new-{}.toml", "pyproject", counter);
-            counter += 1;
-            output_path = PathBuf::from(&filename);
-        }
+    while output_path.exists() {
+        info!(
+            "File {} already exists, creating new",
+            output_path.display()
+        );
+        let filename = format!("{}-new-{}.toml", stem, counter);
+        counter += 1;
+        output_path = requested_path.with_file_name(filename);
     }
-    output_path.to_str().unwrap().to_string()
+    output_path
 }
 
 #[cfg(test)]
@@ -39,50 +44,66 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_get_output_file_name() {
-        let file_name = get_output_file_name(false);
-        assert_eq!(file_name, "pyproject.toml".to_string());
+    fn test_requested_output_path() {
+        let input = Path::new("project/Pipfile");
+
+        assert_eq!(
+            requested_output_path(None, input),
+            PathBuf::from("project/pyproject.toml")
+        );
+        assert_eq!(
+            requested_output_path(Some(Path::new("output/")), input),
+            PathBuf::from("output/pyproject.toml")
+        );
+        assert_eq!(
+            requested_output_path(Some(Path::new("uv.toml")), input),
+            PathBuf::from("uv.toml")
+        );
     }
 
     #[test]
-    fn test_get_output_file_name_docker() {
-        let file_name = get_output_file_name(true);
-
-        assert_eq!(file_name, "output/pyproject.toml");
+    fn test_get_output_file_name() {
+        let file_name = get_output_file_name(Path::new("missing/pyproject.toml"), false);
+        assert_eq!(file_name, PathBuf::from("missing/pyproject.toml"));
     }
 
     #[test]
     fn test_get_output_file_name_existing_file() {
-        std::fs::File::create("pyproject.toml").unwrap();
+        std::fs::create_dir_all("existing").unwrap();
+        std::fs::File::create("existing/pyproject.toml").unwrap();
 
-        let new_file_name = get_output_file_name(false);
+        let new_file_name = get_output_file_name(Path::new("existing/pyproject.toml"), false);
+        let forced_file_name = get_output_file_name(Path::new("existing/pyproject.toml"), true);
 
         // cleaning up
-        std::fs::remove_file("pyproject.toml").unwrap();
+        std::fs::remove_dir_all("existing").unwrap();
 
-        assert_ne!(new_file_name, "pyproject.toml".to_string());
-        assert_eq!(new_file_name, "pyproject-new-1.toml");
+        assert_eq!(
+            new_file_name,
+            PathBuf::from("existing/pyproject-new-1.toml")
+        );
+        assert_eq!(forced_file_name, PathBuf::from("existing/pyproject.toml"));
     }
 
     #[test]
-
-    fn test_get_output_file_name_existing_file_docker() {
+    fn test_get_output_file_name_several_existing_files() {
         std::fs::create_dir_all("output").unwrap();
         std::fs::File::create("output/pyproject.toml").unwrap();
 
-        let first_file_name = get_output_file_name(true);
+        let first_file_name = get_output_file_name(Path::new("output/pyproject.toml"), false);
         std::fs::File::create("output/pyproject-new-1.toml").unwrap();
-        let second_file_name = get_output_file_name(true);
+        let second_file_name = get_output_file_name(Path::new("output/pyproject.toml"), false);
 
-        // teardown since new_file_name is just a string
-        std::fs::remove_file("output/pyproject.toml").unwrap();
-        std::fs::remove_file("output/pyproject-new-1.toml").unwrap();
+        // teardown since new_file_name is just a path
         std::fs::remove_dir_all("output").unwrap();
 
-        assert_ne!(first_file_name, "output/pyproject.toml".to_string());
-        assert_eq!(first_file_name, "output/pyproject-new-1.toml");
-
-        assert_ne!(second_file_name, "output/pyproject.toml".to_string());
-        assert_eq!(second_file_name, "output/pyproject-new-2.toml");
+        assert_eq!(
+            first_file_name,
+            PathBuf::from("output/pyproject-new-1.toml")
+        );
+        assert_eq!(
+            second_file_name,
+            PathBuf::from("output/pyproject-new-2.toml")
+        );
     }
 }